@@ -3,7 +3,7 @@ use crate::{
   svg::client_to_svg,
 };
 
-use gmap::{grids::hex, Alphas, Dart, GMap, OrbitMap};
+use gmap::{grids::hex, Alphas, Dart, GMap, GMapLike, OrbitMap};
 
 use itertools::chain;
 use sauron::{
@@ -0,0 +1,237 @@
+use crate::{Dart, GMap, GMapError, GMapLike};
+
+use std::collections::HashMap;
+
+/// A 2-map built from a description of an embedded graph,
+/// with darts for the vertices and faces of the description.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+  pub g: GMap,
+  /// A dart of each vertex, indexed by vertex number, or None for vertices on no edge
+  pub vertices: Vec<Option<Dart>>,
+  /// The dart of each face at its first vertex, on the side going to its second vertex
+  pub faces: Vec<Dart>,
+}
+
+/// Build a 2-map from faces given by their vertices in order around them.
+/// Faces are sewn along each edge which they share, in whichever direction they traverse it,
+/// so that faces all going around in the same direction give an oriented map.
+/// Edges on only one face are left as boundary.
+///
+/// Since edges are identified by their ends, the graph can have no loops or multiple edges.
+/// The faces must make up a surface: each edge can be on at most two sides of faces,
+/// and the faces around each vertex must form a single fan.
+pub fn from_faces(faces: &[Vec<usize>]) -> Result<Embedding, GMapError> {
+  let invalid = |s: String| Err(GMapError::InvalidEmbedding(s));
+  let mut g = GMap::empty(2)?;
+  let mut darts = vec![];
+  let mut vertex_of = HashMap::new();
+  // the darts at the lower end of each edge, one for each side
+  let mut sides: HashMap<(usize, usize), Vec<Dart>> = HashMap::new();
+  for (k, corners) in faces.iter().enumerate() {
+    if corners.is_empty() {
+      return invalid(format!("face {} has no vertices", k));
+    }
+    let f = g.add_polygon(corners.len());
+    darts.push(f);
+    for (j, x) in g.cycle(f, &[0, 1]).enumerate() {
+      let (p, q) = (corners[j], corners[(j + 1) % corners.len()]);
+      if p == q {
+        return invalid(format!("face {} has a loop at vertex {}", k, p));
+      }
+      vertex_of.insert(x, p);
+      vertex_of.insert(g[(x, 0)], q);
+      let lower = if p < q { x } else { g[(x, 0)] };
+      sides.entry((p.min(q), p.max(q))).or_default().push(lower);
+    }
+  }
+  for (&(p, q), ds) in sides.iter() {
+    match ds[..] {
+      [_] => {}
+      [x, y] => {
+        g.sew(2, x, y)?;
+      }
+      _ => {
+        return invalid(format!(
+          "edge from {} to {} is on {} sides of faces",
+          p,
+          q,
+          ds.len()
+        ))
+      }
+    }
+  }
+
+  let n = vertex_of.values().map(|&v| v + 1).max().unwrap_or(0);
+  let mut vertices = vec![None; n];
+  let mut counts = vec![0; n];
+  for (&x, &v) in vertex_of.iter() {
+    counts[v] += 1;
+    vertices[v] = Some(vertices[v].map_or(x, |y: Dart| y.min(x)));
+  }
+  for (v, d) in vertices.iter().enumerate() {
+    if let Some(d) = d {
+      if g.cell(*d, 0).count() != counts[v] {
+        return invalid(format!(
+          "faces around vertex {} do not form a single fan",
+          v
+        ));
+      }
+    }
+  }
+  Ok(Embedding {
+    g,
+    vertices,
+    faces: darts,
+  })
+}
+
+/// Build a closed 2-map from a rotation system:
+/// the neighbors of each vertex, in counterclockwise order.
+/// Faces are traced counterclockwise, keeping each face to the left of its edges,
+/// and are numbered in order of the first vertex and neighbor from which they are traced.
+///
+/// Each neighbor relation must be listed at both ends, with no loops or multiple edges.
+pub fn from_rotation_system(rotations: &[Vec<usize>]) -> Result<Embedding, GMapError> {
+  let invalid = |s: String| Err(GMapError::InvalidEmbedding(s));
+  // position of each neighbor in each vertex's rotation
+  let mut position = HashMap::new();
+  for (u, neighbors) in rotations.iter().enumerate() {
+    for (k, &v) in neighbors.iter().enumerate() {
+      if v == u {
+        return invalid(format!("vertex {} has a loop", u));
+      }
+      if position.insert((u, v), k).is_some() {
+        return invalid(format!("vertex {} has {} as a neighbor twice", u, v));
+      }
+    }
+  }
+  for &(u, v) in position.keys() {
+    if !rotations.get(v).is_some_and(|ns| ns.contains(&u)) {
+      return invalid(format!(
+        "vertex {} has {} as a neighbor but not the other way around",
+        u, v
+      ));
+    }
+  }
+
+  let mut faces = vec![];
+  let mut traced = HashMap::new();
+  for (u, neighbors) in rotations.iter().enumerate() {
+    for &v in neighbors {
+      if traced.contains_key(&(u, v)) {
+        continue;
+      }
+      let mut face = vec![];
+      let (mut a, mut b) = (u, v);
+      while traced.insert((a, b), faces.len()).is_none() {
+        face.push(a);
+        let ns = &rotations[b];
+        let k = position[&(b, a)];
+        (a, b) = (b, ns[(k + ns.len() - 1) % ns.len()]);
+      }
+      faces.push(face);
+    }
+  }
+
+  let mut e = from_faces(&faces)?;
+  e.vertices.resize(rotations.len(), None);
+  Ok(e)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::topology::{self, Surface};
+
+  #[test]
+  fn test_from_faces() {
+    let cube = [
+      vec![0, 1, 2, 3],
+      vec![4, 7, 6, 5],
+      vec![0, 4, 5, 1],
+      vec![1, 5, 6, 2],
+      vec![2, 6, 7, 3],
+      vec![3, 7, 4, 0],
+    ];
+    let e = from_faces(&cube).unwrap();
+    let c = topology::components(&e.g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (8, 12, 6));
+    assert_eq!(c[0].surface(), Some(Surface::SPHERE));
+    assert!(e.g.is_oriented());
+    assert_eq!(e.vertices.len(), 8);
+    for (f, corners) in e.faces.iter().zip(&cube) {
+      let v0 = e.vertices[corners[0]].unwrap();
+      let v1 = e.vertices[corners[1]].unwrap();
+      assert!(e.g.cell(v0, 0).any(|x| x == *f));
+      assert!(e.g.cell(v1, 0).any(|x| x == e.g[(*f, 0)]));
+    }
+
+    // faces going around in different directions are still sewn
+    let e = from_faces(&[vec![0, 1, 2], vec![0, 1, 3]]).unwrap();
+    let c = topology::components(&e.g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::DISK));
+    assert!(!e.g.is_oriented());
+
+    let e = from_faces(&[vec![1, 2, 3]]).unwrap();
+    assert_eq!(e.vertices[0], None);
+
+    for bad in [
+      vec![vec![0, 1, 2], vec![1, 0, 3], vec![0, 1, 4]],
+      vec![vec![0, 1, 2], vec![0, 3, 4]],
+      vec![vec![0, 0, 1]],
+      vec![vec![]],
+    ] {
+      assert!(matches!(
+        from_faces(&bad),
+        Err(GMapError::InvalidEmbedding(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn test_from_rotation_system() {
+    // K4 drawn with 0 in the middle of the triangle 1, 2, 3
+    let e =
+      from_rotation_system(&[vec![1, 2, 3], vec![2, 0, 3], vec![3, 0, 1], vec![1, 0, 2]]).unwrap();
+    let c = topology::components(&e.g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (4, 6, 4));
+    assert_eq!(c[0].surface(), Some(Surface::SPHERE));
+    assert!(e
+      .g
+      .one_dart_per_cell(2)
+      .all(|f| e.g.cycle(f, &[0, 1]).count() == 3));
+    assert!(e.g.is_oriented());
+    // the first face is traced from 0 to 1, and continues counterclockwise to 2
+    let f = e.faces[0];
+    assert!(e.g.cell(e.vertices[0].unwrap(), 0).any(|x| x == f));
+    assert!(e
+      .g
+      .cell(e.vertices[2].unwrap(), 0)
+      .any(|x| x == e.g.al(f, [0, 1, 0])));
+
+    // reversing the rotation at one vertex puts K4 on a torus
+    let e =
+      from_rotation_system(&[vec![1, 3, 2], vec![2, 0, 3], vec![3, 0, 1], vec![1, 0, 2]]).unwrap();
+    let c = topology::components(&e.g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::TORUS));
+
+    // a path, with an isolated vertex
+    let e = from_rotation_system(&[vec![1], vec![0, 2], vec![1], vec![]]).unwrap();
+    let c = topology::components(&e.g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (3, 2, 1));
+    assert_eq!(e.vertices.len(), 4);
+    assert_eq!(e.vertices[3], None);
+
+    for bad in [
+      vec![vec![1], vec![]],
+      vec![vec![0]],
+      vec![vec![1, 1], vec![0, 0]],
+    ] {
+      assert!(matches!(
+        from_rotation_system(&bad),
+        Err(GMapError::InvalidEmbedding(_))
+      ));
+    }
+  }
+}
@@ -0,0 +1,375 @@
+use crate::{Dart, GMap, GMapError, GMapLike};
+
+use std::collections::HashMap;
+
+/// Where a dart of the result of a Conway operator comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance<D> {
+  /// Dart of the original map whose triangle (in the barycentric subdivision) the new dart lies in
+  /// or next to
+  pub dart: D,
+  /// cells[i] is the dimension of the cell of `dart` whose center is the center of the new dart's i-cell,
+  /// or None if the new i-cell is not centered on a cell of the original map.
+  pub cells: [Option<usize>; 3],
+}
+
+/// The new map, and the provenance of each of its darts indexed by dart number.
+pub type Output<D> = (GMap, Vec<Provenance<D>>);
+
+/// alpha_i of a dart of a pattern.
+#[derive(Debug, Clone, Copy)]
+enum Link {
+  /// Another dart of the same tile
+  Inner(usize),
+  /// Outer(h, j, k) is dart k of the neighboring tile across alpha_j of the tile's h-th dart
+  Outer(usize, usize, usize),
+}
+
+use Link::{Inner, Outer};
+
+/// A dart of the pattern that an operator puts in each tile of the original map.
+#[derive(Debug, Clone, Copy)]
+struct Flag {
+  /// Which dart of the tile the new dart comes from
+  half: usize,
+  cells: [Option<usize>; 3],
+  links: [Link; 3],
+}
+
+const fn flag(cells: [Option<usize>; 3], links: [Link; 3]) -> Flag {
+  Flag {
+    half: 0,
+    cells,
+    links,
+  }
+}
+
+const V: Option<usize> = Some(0);
+const E: Option<usize> = Some(1);
+const F: Option<usize> = Some(2);
+const N: Option<usize> = None;
+
+// Patterns for the achiral operators, with each dart of g as a tile:
+// the triangle between the dart's vertex, the middle of its edge and the center of its face,
+// which is reflected into the tile of alpha_j of the dart across the side opposite the corner
+// at the center of its j-cell.
+
+/// New vertices at the middle of each edge, with a new edge across each angle.
+const AMBO: [Flag; 2] = [
+  flag([E, N, V], [Outer(0, 1, 0), Outer(0, 2, 0), Inner(1)]),
+  flag([E, N, F], [Outer(0, 1, 1), Outer(0, 0, 1), Inner(0)]),
+];
+
+/// New vertices on each edge near each end, with a new edge across each angle.
+const TRUNCATE: [Flag; 3] = [
+  flag([N, N, V], [Outer(0, 1, 0), Outer(0, 2, 0), Inner(1)]),
+  flag([N, N, F], [Outer(0, 1, 1), Inner(2), Inner(0)]),
+  flag([N, E, F], [Outer(0, 0, 2), Inner(1), Outer(0, 2, 2)]),
+];
+
+/// New vertex at the center of each face, joined to each of its corners.
+const KIS: [Flag; 3] = [
+  flag([V, E, N], [Outer(0, 0, 0), Inner(1), Outer(0, 2, 0)]),
+  flag([V, N, N], [Inner(2), Inner(0), Outer(0, 1, 1)]),
+  flag([F, N, N], [Inner(1), Outer(0, 0, 2), Outer(0, 1, 2)]),
+];
+
+/// New edge from each vertex to each incident face's center, replacing the old edges.
+const JOIN: [Flag; 2] = [
+  flag([V, N, E], [Inner(1), Outer(0, 2, 0), Outer(0, 1, 0)]),
+  flag([F, N, E], [Inner(0), Outer(0, 0, 1), Outer(0, 1, 1)]),
+];
+
+/// New vertices at the middle of each edge and the center of each face,
+/// with a new quadrilateral for each angle.
+const ORTHO: [Flag; 4] = [
+  flag([V, N, N], [Inner(1), Outer(0, 1, 0), Outer(0, 2, 0)]),
+  flag([E, N, N], [Inner(0), Inner(2), Outer(0, 2, 1)]),
+  flag([E, N, N], [Inner(3), Inner(1), Outer(0, 0, 2)]),
+  flag([F, N, N], [Inner(2), Outer(0, 1, 3), Outer(0, 0, 3)]),
+];
+
+/// New vertex for each angle, with a new face for each vertex, edge and face.
+const EXPAND: [Flag; 4] = [
+  flag([N, N, V], [Outer(0, 2, 0), Outer(0, 1, 0), Inner(1)]),
+  flag([N, N, E], [Outer(0, 2, 1), Inner(2), Inner(0)]),
+  flag([N, N, E], [Outer(0, 0, 2), Inner(1), Inner(3)]),
+  flag([N, N, F], [Outer(0, 0, 3), Outer(0, 1, 3), Inner(2)]),
+];
+
+/// Pattern for gyro, with a tile for each dart u of one orientation together with alpha_0(u):
+/// the triangle between u's vertex v, the other vertex v' of the edge, and the center c of the face.
+/// Each edge gets two new vertices a near v and a' near v', and the new edge a' c
+/// splits the tile into a quadrilateral v a a' c and a triangle a' v' c,
+/// which make up a pentagon with the triangle of the tile across v c.
+/// The tile is rotated into the tile across v c, v' c or the edge,
+/// which are reached by alpha_1 of u, alpha_1 of alpha_0(u) or alpha_2.
+const GYRO: [Flag; 10] = [
+  // the quadrilateral, going from v to c
+  gyro_flag(0, [V, N, N], [Inner(1), Outer(0, 1, 6), Outer(0, 2, 6)]),
+  gyro_flag(0, [N, N, N], [Inner(0), Inner(2), Outer(0, 2, 7)]),
+  gyro_flag(0, [N, E, N], [Inner(3), Inner(1), Outer(0, 2, 3)]),
+  gyro_flag(0, [N, E, N], [Inner(2), Inner(4), Outer(0, 2, 2)]),
+  gyro_flag(0, [N, N, N], [Inner(5), Inner(3), Inner(8)]),
+  gyro_flag(1, [F, N, N], [Inner(4), Outer(0, 1, 9), Inner(9)]),
+  // the triangle, going from v' to c
+  gyro_flag(1, [V, N, N], [Inner(7), Outer(1, 1, 0), Outer(1, 2, 0)]),
+  gyro_flag(1, [N, N, N], [Inner(6), Inner(8), Outer(1, 2, 1)]),
+  gyro_flag(1, [N, N, N], [Inner(9), Inner(7), Inner(4)]),
+  gyro_flag(1, [F, N, N], [Inner(8), Outer(1, 1, 5), Inner(5)]),
+];
+
+const fn gyro_flag(half: usize, cells: [Option<usize>; 3], links: [Link; 3]) -> Flag {
+  Flag { half, cells, links }
+}
+
+/// The pattern of the operator followed by taking the dual,
+/// which swaps the new vertices and faces.
+fn dual(pattern: &[Flag]) -> Vec<Flag> {
+  pattern
+    .iter()
+    .map(|&Flag { half, cells, links }| Flag {
+      half,
+      cells: [cells[2], cells[1], cells[0]],
+      links: [links[2], links[1], links[0]],
+    })
+    .collect()
+}
+
+/// Replace each tile of g by a copy of the pattern.
+/// Darts are numbered by tile and then by position in the pattern.
+/// Links across free darts of g are free.
+fn decorate<G: GMapLike + ?Sized>(
+  g: &G,
+  tiles: &[Vec<G::Dart>],
+  pattern: &[Flag],
+) -> Result<Output<G::Dart>, GMapError> {
+  if g.dimension() != 2 {
+    return Err(GMapError::WrongDimension(2));
+  }
+  let tile: HashMap<G::Dart, usize> = tiles
+    .iter()
+    .enumerate()
+    .flat_map(|(t, ds)| ds.iter().map(move |&d| (d, t)))
+    .collect();
+  let mut h = GMap::empty(2)?;
+  let mut provenance = vec![];
+  for ds in tiles {
+    for p in pattern {
+      h.add_dart();
+      provenance.push(Provenance {
+        dart: ds[p.half],
+        cells: p.cells,
+      });
+    }
+  }
+  let k = pattern.len();
+  for (t, ds) in tiles.iter().enumerate() {
+    for (f, p) in pattern.iter().enumerate() {
+      let x = Dart(t * k + f);
+      for (i, &link) in p.links.iter().enumerate() {
+        *h.al1(x, i) = match link {
+          Inner(f1) => Dart(t * k + f1),
+          Outer(half, j, f1) => {
+            let d = ds[half];
+            let y = g.alpha(d, j);
+            if y == d {
+              x
+            } else {
+              Dart(tile[&y] * k + f1)
+            }
+          }
+        };
+      }
+    }
+  }
+  debug_assert!(h.check_valid().is_ok());
+  Ok((h, provenance))
+}
+
+/// Each dart of g as its own tile.
+fn flags<G: GMapLike + ?Sized>(g: &G) -> Vec<Vec<G::Dart>> {
+  g.darts().map(|d| vec![d]).collect()
+}
+
+/// Tiles made of the darts of g of one orientation, each together with its alpha_0.
+fn oriented_tiles<G: GMapLike + ?Sized>(g: &G) -> Result<Vec<Vec<G::Dart>>, GMapError> {
+  let mut color = HashMap::new();
+  for d in g.one_dart_per_component() {
+    color.extend(g.orientation(d)?);
+  }
+  if g.darts().any(|x| g.is_free(x, 0)) {
+    return Err(GMapError::OpenEdge);
+  }
+  Ok(
+    g.darts()
+      .filter(|x| color[x])
+      .map(|x| vec![x, g.alpha(x, 0)])
+      .collect(),
+  )
+}
+
+/// Ambo (rectification) of a 2-map: vertices at the middle of each edge,
+/// with a face for each face and each vertex of g.
+/// As for the other operators, free darts of g give free darts of the result,
+/// so that cells along the boundary of g may be open.
+pub fn ambo<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &flags(g), &AMBO)
+}
+
+/// Truncation of a 2-map: each vertex is cut off, becoming a face.
+pub fn truncate<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &flags(g), &TRUNCATE)
+}
+
+/// Kis of a 2-map: each face is split into triangles by a new vertex at its center.
+/// This is the dual of the truncation of the dual.
+pub fn kis<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &flags(g), &KIS)
+}
+
+/// Join of a 2-map: a quadrilateral for each edge,
+/// between its two vertices and the centers of its two faces.
+/// This is the dual of ambo.
+pub fn join<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &flags(g), &JOIN)
+}
+
+/// Ortho of a 2-map: a quadrilateral for each angle,
+/// between its vertex, the middles of its two edges, and the center of its face.
+/// This is join applied twice.
+pub fn ortho<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &flags(g), &ORTHO)
+}
+
+/// Expansion (cantellation) of a 2-map: the faces are pulled apart,
+/// with a quadrilateral for each edge and a face for each vertex in between.
+/// This is ambo applied twice, and the dual of ortho.
+pub fn expand<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &flags(g), &EXPAND)
+}
+
+/// Gyro of an orientable 2-map: each n-gon becomes n pentagons around its center,
+/// each edge being split in three.
+/// The pentagons turn the same way relative to the orientation of each component of g.
+/// Returns `GMapError::OpenEdge` if a dart of g is 0-free.
+pub fn gyro<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &oriented_tiles(g)?, &GYRO)
+}
+
+/// Snub of an orientable 2-map: the faces are pulled apart and twisted,
+/// with a face for each vertex and two triangles for each edge in between.
+/// This is the dual of gyro.
+/// Returns `GMapError::OpenEdge` if a dart of g is 0-free.
+pub fn snub<G: GMapLike + ?Sized>(g: &G) -> Result<Output<G::Dart>, GMapError> {
+  decorate(g, &oriented_tiles(g)?, &dual(&GYRO))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::polyhedron::{polyhedron, Solid};
+  use crate::grids::{hex, square, Wrap};
+  use crate::isomorphism::is_isomorphic;
+  use crate::topology::{self, Surface};
+
+  fn counts(g: &GMap) -> (usize, usize, usize) {
+    let c = topology::components(g).unwrap();
+    assert_eq!(c.len(), 1);
+    (c[0].vertices, c[0].edges, c[0].faces)
+  }
+
+  #[test]
+  fn test_operators() {
+    let cube = polyhedron(Solid::Cube).unwrap().g;
+    let cases: [(fn(&GMap) -> _, _); 8] = [
+      (ambo, (12, 24, 14)),
+      (truncate, (24, 36, 14)),
+      (kis, (14, 36, 24)),
+      (join, (14, 24, 12)),
+      (ortho, (26, 48, 24)),
+      (expand, (24, 48, 26)),
+      (gyro, (38, 60, 24)),
+      (snub, (24, 60, 38)),
+    ];
+    for (op, vef) in cases {
+      let (h, provenance): (GMap, Vec<Provenance<Dart>>) = op(&cube).unwrap();
+      assert_eq!(counts(&h), vef);
+      assert_eq!(
+        topology::components(&h).unwrap()[0].surface(),
+        Some(Surface::SPHERE)
+      );
+      assert_eq!(provenance.len(), h.darts().count());
+    }
+
+    let same = |g: GMap, s: Solid| assert!(is_isomorphic(&g, &polyhedron(s).unwrap().g), "{:?}", s);
+    same(ambo(&cube).unwrap().0, Solid::Cuboctahedron);
+    same(truncate(&cube).unwrap().0, Solid::TruncatedCube);
+    same(expand(&cube).unwrap().0, Solid::Rhombicuboctahedron);
+    same(snub(&cube).unwrap().0, Solid::SnubCube);
+    let tetrahedron = polyhedron(Solid::Tetrahedron).unwrap().g;
+    same(ambo(&tetrahedron).unwrap().0, Solid::Octahedron);
+    same(
+      truncate(&tetrahedron).unwrap().0,
+      Solid::TruncatedTetrahedron,
+    );
+    same(
+      kis(&tetrahedron).unwrap().0.dual(),
+      Solid::TruncatedTetrahedron,
+    );
+    same(gyro(&tetrahedron).unwrap().0, Solid::Dodecahedron);
+    same(snub(&tetrahedron).unwrap().0, Solid::Icosahedron);
+    let dodecahedron = polyhedron(Solid::Dodecahedron).unwrap().g;
+    same(snub(&dodecahedron).unwrap().0, Solid::SnubDodecahedron);
+    same(
+      truncate(&dodecahedron.dual()).unwrap().0,
+      Solid::TruncatedIcosahedron,
+    );
+    same(
+      join(&dodecahedron).unwrap().0.dual(),
+      Solid::Icosidodecahedron,
+    );
+    same(
+      ortho(&dodecahedron).unwrap().0.dual(),
+      Solid::Rhombicosidodecahedron,
+    );
+  }
+
+  #[test]
+  fn test_provenance() {
+    let (g, _) = hex::new(2, 3);
+    let (h, provenance) = truncate(&g).unwrap();
+    for x in h.darts() {
+      let p = &provenance[x.0];
+      // the new dart's cells which are centered on cells of g contain the images of those cells
+      for i in 0..3 {
+        if let Some(c) = p.cells[i] {
+          for y in h.cell(x, i) {
+            let q = &provenance[y.0];
+            assert_eq!(q.cells[i], Some(c));
+            assert!(g.cell(p.dart, c).any(|z| z == q.dart));
+          }
+        }
+      }
+    }
+    // each vertex of g becomes a face of the truncation
+    let vertex_faces = h
+      .one_dart_per_cell(2)
+      .filter(|f| provenance[f.0].cells[2] == Some(0))
+      .count();
+    assert_eq!(vertex_faces, g.one_dart_per_cell(0).count());
+
+    let (g, _) = square::new(2, 2);
+    let (h, _) = ambo(&g).unwrap();
+    assert_eq!(h.one_dart_per_cell(0).count(), 12);
+    assert!(h.orientation(Dart(0)).is_ok());
+
+    let (g, _) = square::wrapped(2, 2, Wrap::Straight, Wrap::Twisted);
+    assert!(matches!(gyro(&g), Err(GMapError::NonOrientable)));
+    assert_eq!(counts(&expand(&g).unwrap().0).2, 4 + 8 + 4);
+
+    let mut g = GMap::empty(2).unwrap();
+    g.add_dart();
+    assert!(matches!(snub(&g), Err(GMapError::OpenEdge)));
+  }
+}
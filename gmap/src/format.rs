@@ -1,6 +1,6 @@
 use crate::Dart;
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -8,13 +8,13 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize)]
 pub struct GMap {
   dimension: usize,
-  alpha: HashMap<Dart, Vec<Dart>>,
+  alpha: BTreeMap<Dart, Vec<Dart>>,
 }
 
 impl From<crate::GMap> for GMap {
   fn from(g: crate::GMap) -> Self {
-    let alpha: HashMap<Dart, Vec<Dart>> = g
-      .alpha()
+    let alpha: BTreeMap<Dart, Vec<Dart>> = g
+      .alpha_slice()
       .iter()
       .cloned()
       .chunks(g.dimension + 1)
@@ -38,7 +38,7 @@ impl From<crate::GMap> for GMap {
 impl TryFrom<GMap> for crate::GMap {
   type Error = crate::GMapError;
   fn try_from(o: GMap) -> Result<Self, Self::Error> {
-    Self::from_alpha(o.dimension, o.alpha)
+    Self::from_alpha(o.dimension, o.alpha.into_iter().collect())
   }
 }
 
@@ -1,4 +1,7 @@
-use crate::{Alphas, Dart, GMap, OrbitMap};
+use super::{
+  glue_sides, mask_of, masked, opposite_side, polygon_alpha, GridCoords, GridDart, Wrap,
+};
+use crate::{topology, Alphas, Dart, GMap, GMapLike, OrbitMap};
 
 use itertools::Itertools;
 
@@ -28,6 +31,83 @@ pub fn new(n: usize, m: usize) -> (GMap, Vec<Vec<Dart>>) {
   (g, rows)
 }
 
+/// Grid of hexes as for `new`, with the first row (r = 0) joined to the last according to wrap_rows,
+/// and the first column (q = 0) joined to the last according to wrap_cols.
+/// For example, wrapping both straight gives a torus,
+/// and wrapping one straight and the other twisted gives a Klein bottle.
+///
+/// Since the grid is a rhombus, the sides are staggered,
+/// and the few edges near the corners which don't line up are left as boundary
+/// unless both axes are wrapped.
+/// Every vertex has degree 3 except when both axes are twisted (a projective plane),
+/// which no grid of hexes can cover regularly.
+///
+/// Returns None if the columns are twisted and the rows are not wrapped with only one row,
+/// since then no edges of the twisted sides line up.
+pub fn wrapped(
+  n: usize,
+  m: usize,
+  wrap_rows: Wrap,
+  wrap_cols: Wrap,
+) -> Option<(GMap, Vec<Vec<Dart>>)> {
+  if n == 1 && wrap_rows == Wrap::None && wrap_cols == Wrap::Twisted {
+    return None;
+  }
+  let (mut g, rows) = new(n, m);
+  if n == 0 || m == 0 {
+    return Some((g, rows));
+  }
+  // clockwise from the west end of the northwest edge of the northwest hex
+  let boundary = topology::boundary(&g, g.al(rows[0][0], [1, 0]));
+  let (north, rest) = boundary.split_at(2 * m);
+  let (east, rest) = rest.split_at(2 * n - 1);
+  let (south, west) = rest.split_at(2 * m);
+  let (south, south_corner) = (&south[..2 * m - 1], south[2 * m - 1]);
+  let (north, north_corner) = match wrap_rows {
+    Wrap::Twisted => (&north[1..], north[0]),
+    _ => (&north[..2 * m - 1], north[2 * m - 1]),
+  };
+  // edges near the corners which don't line up, joined if both axes are wrapped
+  let (east, west, corners) = match wrap_cols {
+    Wrap::Twisted => {
+      let flip = match wrap_rows {
+        Wrap::Twisted => Wrap::Straight,
+        _ => Wrap::Twisted,
+      };
+      (
+        &east[1..],
+        &west[..2 * n - 2],
+        vec![
+          (west[2 * n - 2], north_corner, flip),
+          (east[0], south_corner, Wrap::Twisted),
+        ],
+      )
+    }
+    _ => (east, west, vec![(north_corner, south_corner, wrap_rows)]),
+  };
+  glue_sides(&mut g, east, west, wrap_cols);
+  glue_sides(&mut g, north, south, wrap_rows);
+  if wrap_rows != Wrap::None && wrap_cols != Wrap::None {
+    for (d0, d1, wrap) in corners {
+      glue_sides(&mut g, &[d0], &[d1], wrap);
+    }
+  }
+  Some((g, rows))
+}
+
+/// Grid of the hexes where mask[r][q] is true, in the same coordinates as `new`,
+/// with hexes sewn together wherever they are adjacent, so that the mask may have holes.
+/// Returns the table of hexes as for `new`, with None for absent hexes.
+pub fn from_mask(mask: &[Vec<bool>]) -> (GMap, Vec<Vec<Option<Dart>>>) {
+  let cols = mask.iter().map(|row| row.len()).max().unwrap_or(0);
+  masked(&HexGrid::new(mask.len(), cols), mask)
+}
+
+/// Grid of the hexes at the given (r, q) coordinates, as for `from_mask`.
+pub fn from_cells(cells: &[(usize, usize)]) -> (GMap, Vec<Vec<Option<Dart>>>) {
+  from_mask(&mask_of(cells))
+}
+
 /// Returns coordinates along basis vectors (a, b) where a + b = (0, 1),
 /// 2a - b = (1, 0)
 /// That is, a and b are 15 degrees rotated from the r- and q- axes,
@@ -50,3 +130,266 @@ pub fn vertex_coords(g: &GMap, rows: &[Vec<Dart>]) -> OrbitMap<(isize, isize)> {
   }
   coords
 }
+
+/// Coordinates of the cells of a grid from `new` or `wrapped`, given its table of hexes,
+/// as located by `HexGrid`: vertices as in `vertex_coords` and edges in doubled coordinates.
+pub fn coords(g: &GMap, hexes: &[Vec<Dart>]) -> GridCoords {
+  let grid = HexGrid::new(hexes.len(), hexes.first().map_or(0, |row| row.len()));
+  GridCoords::new(g, hexes, 6, |d| {
+    let (r, q) = grid.f_loc(d);
+    [grid.v_loc(d), grid.e_loc2(d), (r as isize, q as isize)]
+  })
+}
+
+/// Implicit grid of hexes with (0 <= r < n, 0 <= q < m), in the same coordinates as `new`.
+///
+/// Darts are numbered within each hex starting from the north end of the northeast edge,
+/// going clockwise: 0 and 1 on the northeast edge, 2 and 3 on the east edge, and so on.
+/// Dart 0 of each hex corresponds to the darts returned by `new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexGrid {
+  pub rows: usize,
+  pub cols: usize,
+}
+
+/// (r, q) offset of the neighbor across each side
+const NEIGHBORS: [(isize, isize); 6] = [(-1, 1), (0, 1), (1, 0), (1, -1), (0, -1), (-1, 0)];
+/// offset of each vertex, clockwise from north, in the basis of `vertex_coords`
+const VERTEX_OFFSETS: [(isize, isize); 6] = [(0, -1), (1, -1), (1, 0), (0, 1), (-1, 1), (-1, 0)];
+
+impl HexGrid {
+  pub fn new(rows: usize, cols: usize) -> Self {
+    Self { rows, cols }
+  }
+
+  pub fn has_hex(&self, r: isize, q: isize) -> bool {
+    0 <= r && r < self.rows as isize && 0 <= q && q < self.cols as isize
+  }
+
+  /// Dart 0 of the hex at (r, q)
+  pub fn face(&self, r: usize, q: usize) -> Option<GridDart> {
+    if self.has_hex(r as isize, q as isize) {
+      Some(GridDart {
+        row: r,
+        col: q,
+        i: 0,
+      })
+    } else {
+      None
+    }
+  }
+
+  /// location of face
+  pub fn f_loc(&self, d: GridDart) -> (usize, usize) {
+    (d.row, d.col)
+  }
+
+  /// location of vertex, in the same coordinates as `vertex_coords`
+  pub fn v_loc(&self, d: GridDart) -> (isize, isize) {
+    let r = d.row as isize;
+    let q = d.col as isize;
+    let (ao, bo) = VERTEX_OFFSETS[(d.i / 2 + d.i % 2) % 6];
+    (r + 2 * q + ao, r - q + bo)
+  }
+
+  /// location of edge in doubled coordinates:
+  /// the sum of the locations of its endpoints
+  pub fn e_loc2(&self, d: GridDart) -> (isize, isize) {
+    let (a0, b0) = self.v_loc(d);
+    let (a1, b1) = self.v_loc(self.alpha(d, 0));
+    (a0 + a1, b0 + b1)
+  }
+
+  /// Convert to the explicit representation,
+  /// also returning the table of hexes in the same format as `new`.
+  pub fn to_explicit(&self) -> (GMap, Vec<Vec<Dart>>) {
+    let (g, m) = self.to_gmap();
+    let rows = (0..self.rows)
+      .map(|r| {
+        (0..self.cols)
+          .map(|q| {
+            m[&GridDart {
+              row: r,
+              col: q,
+              i: 0,
+            }]
+          })
+          .collect()
+      })
+      .collect();
+    (g, rows)
+  }
+}
+
+impl GMapLike for HexGrid {
+  type Dart = GridDart;
+
+  fn dimension(&self) -> usize {
+    2
+  }
+
+  fn darts(&self) -> impl Iterator<Item = GridDart> + '_ {
+    (0..self.rows)
+      .cartesian_product(0..self.cols)
+      .cartesian_product(0..12)
+      .map(|((row, col), i)| GridDart { row, col, i })
+  }
+
+  fn alpha(&self, d: GridDart, j: usize) -> GridDart {
+    match j {
+      0 | 1 => GridDart {
+        i: polygon_alpha(6, d.i, j),
+        ..d
+      },
+      2 => {
+        let (ro, qo) = NEIGHBORS[d.i / 2];
+        let r = d.row as isize + ro;
+        let q = d.col as isize + qo;
+        if self.has_hex(r, q) {
+          GridDart {
+            row: r as usize,
+            col: q as usize,
+            i: opposite_side(6, d.i),
+          }
+        } else {
+          d
+        }
+      }
+      _ => d,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::collections::HashMap;
+
+  #[test]
+  fn test_implicit_matches_explicit() {
+    let (g, hexes) = new(3, 4);
+    let coords = vertex_coords(&g, &hexes);
+    let grid = HexGrid::new(3, 4);
+    let mut m = HashMap::new();
+    let mut stack = vec![(
+      GridDart {
+        row: 0,
+        col: 0,
+        i: 0,
+      },
+      hexes[0][0],
+    )];
+    while let Some((d, e)) = stack.pop() {
+      if let Some(&e1) = m.get(&d) {
+        assert_eq!(e1, e);
+        continue;
+      }
+      m.insert(d, e);
+      assert_eq!(grid.v_loc(d), coords.map()[&e]);
+      for j in 0..=2 {
+        stack.push((grid.alpha(d, j), g[(e, j)]));
+      }
+    }
+    assert_eq!(m.len(), g.darts().count());
+    for r in 0..3 {
+      for q in 0..4 {
+        assert_eq!(m[&grid.face(r, q).unwrap()], hexes[r][q]);
+      }
+    }
+
+    let (g, _) = grid.to_explicit();
+    assert_eq!(g.one_dart_per_cell(2).count(), 12);
+    assert_eq!(
+      g.one_dart_per_cell(1).count(),
+      grid.one_dart_per_cell(1).count()
+    );
+  }
+
+  #[test]
+  fn test_from_mask() {
+    use crate::isomorphism::is_isomorphic;
+    use crate::topology::{self, Surface};
+
+    let (g, hexes) = from_mask(&vec![vec![true; 4]; 3]);
+    assert!(is_isomorphic(&g, &new(3, 4).0));
+    assert!(hexes.iter().flatten().all(|h| h.is_some()));
+
+    // the six hexes around (1, 1)
+    let cells = [(0, 1), (0, 2), (1, 2), (2, 1), (2, 0), (1, 0)];
+    let (g, hexes) = from_cells(&cells);
+    assert_eq!(hexes[1][1], None);
+    let c = topology::components(&g).unwrap();
+    assert_eq!(c.len(), 1);
+    assert_eq!(c[0].surface(), Some(Surface::CYLINDER));
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (24, 30, 6));
+    let (g1, _) = from_cells(&[(0, 1), (0, 2), (1, 2), (2, 1), (2, 0), (1, 0), (1, 1)]);
+    assert_eq!(
+      topology::components(&g1).unwrap()[0].surface(),
+      Some(Surface::DISK)
+    );
+  }
+
+  #[test]
+  fn test_coords() {
+    let (g, hexes) = new(3, 4);
+    let coords = coords(&g, &hexes);
+    let vertices = vertex_coords(&g, &hexes);
+    for d in g.darts() {
+      assert_eq!(
+        coords.coords_of(d, Alphas::VERTEX),
+        Some(vertices.map()[&d])
+      );
+      for a in [Alphas::VERTEX, Alphas::EDGE, Alphas::FACE] {
+        let p = coords.coords_of(d, a).unwrap();
+        assert!(g.orbit(d, a).contains(&coords.dart_at(a, p).unwrap()));
+      }
+    }
+    assert_eq!(coords.face_at(2, 3), Some(hexes[2][3]));
+    let h = hexes[1][1];
+    let (v0, v1) = (vertices.map()[&h], vertices.map()[&g[(h, 0)]]);
+    for (v0, v1) in [(v0, v1), (v1, v0)] {
+      let d = coords.edge_between(v0, v1).unwrap();
+      assert_eq!(coords.coords_of(d, Alphas::VERTEX), Some(v0));
+      assert!(g.cell(d, 1).any(|x| x == h));
+    }
+    assert_eq!(coords.edge_between(v0, v0), None);
+  }
+
+  #[test]
+  fn test_wrapped() {
+    use crate::topology::{self, Surface};
+    use Wrap::*;
+    for (wrap_rows, wrap_cols, surface) in [
+      (None, None, Surface::DISK),
+      (Straight, None, Surface::CYLINDER),
+      (None, Twisted, Surface::MOBIUS_STRIP),
+      (Straight, Straight, Surface::TORUS),
+      (Twisted, Straight, Surface::KLEIN_BOTTLE),
+      (Straight, Twisted, Surface::KLEIN_BOTTLE),
+      (Twisted, Twisted, Surface::PROJECTIVE_PLANE),
+    ] {
+      for (n, m) in [(2, 2), (2, 3), (4, 3)] {
+        let (g, rows) = wrapped(n, m, wrap_rows, wrap_cols).unwrap();
+        assert_eq!(rows, new(n, m).1);
+        let c = topology::components(&g).unwrap();
+        assert_eq!(c.len(), 1);
+        assert_eq!(
+          c[0].surface(),
+          Some(surface),
+          "{:?} {:?} {}x{}",
+          wrap_rows,
+          wrap_cols,
+          n,
+          m
+        );
+        if c[0].boundaries == 0 && surface != Surface::PROJECTIVE_PLANE {
+          assert!(g.one_dart_per_cell(0).all(|v| g.cell(v, 0).count() == 6));
+        }
+      }
+    }
+    // a single row can only be twisted together with wrapping the rows
+    assert!(wrapped(1, 3, None, Twisted).is_none());
+    assert!(wrapped(1, 3, Straight, Twisted).is_some());
+  }
+}
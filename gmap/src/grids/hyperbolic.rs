@@ -0,0 +1,380 @@
+use crate::{topology, Alphas, Dart, GMap, GMapLike, OrbitMap};
+
+use std::collections::{HashMap, VecDeque};
+use std::f64::consts::PI;
+
+/// A map with the faces of each ring around its central face, and the position of each vertex.
+pub type Rings = (GMap, Vec<Vec<Dart>>, OrbitMap<(f64, f64)>);
+
+/// Patch of the hyperbolic tiling by p-gons meeting q at each vertex,
+/// consisting of a central face and `depth` rings of faces around it,
+/// each ring being the faces which share a vertex with the previous rings.
+///
+/// Returns the map, the faces of each ring (the central face forming ring 0),
+/// and the position of each vertex in the Poincaré disk model, centered on the central face,
+/// or None if the tiling is not hyperbolic, that is unless (p - 2)(q - 2) > 4.
+pub fn rings(p: usize, q: usize, depth: usize) -> Option<Rings> {
+  if p < 3 || q < 3 || (p - 2) * (q - 2) <= 4 {
+    return None;
+  }
+  let mut g = GMap::empty(2).unwrap();
+  let center = g.add_polygon(p);
+  let mut rings = vec![vec![center]];
+  let mut start = center;
+  for _ in 0..depth {
+    let ring = add_ring(&mut g, p, q, start);
+    start = ring
+      .iter()
+      .flat_map(|&f| g.cycle(f, &[0, 1]))
+      .find(|&d| g.is_free(d, 2))
+      .unwrap();
+    rings.push(ring);
+  }
+  let layout = poincare_layout(&g, p, q, center);
+  Some((g, rings, layout))
+}
+
+/// Add the faces around the boundary containing the 2-free dart start,
+/// so that each boundary vertex gets q faces.
+/// start must be the lowest-numbered dart of its polygon, or reached from it by [0, 1],
+/// so that the new faces are oriented consistently.
+/// Returns the new faces in order around the boundary.
+fn add_ring(g: &mut GMap, p: usize, q: usize, start: Dart) -> Vec<Dart> {
+  let xs = topology::boundary(g, start);
+  let len = xs.len();
+  // faces still missing at each boundary vertex
+  let missing: Vec<usize> = xs.iter().map(|&x| q - g.cell(x, 0).count() / 2).collect();
+
+  // runs of boundary edges joined by vertices missing only one face are covered by a single face
+  let j0 = (0..len).find(|&j| missing[j] >= 2).unwrap();
+  let mut runs: Vec<(usize, usize)> = vec![];
+  for j in (j0..len + j0).map(|j| j % len) {
+    match runs.last_mut() {
+      Some((_, l)) if missing[j] == 1 => *l += 1,
+      _ => runs.push((j, 1)),
+    }
+  }
+
+  // each new face is walked from an upper dart, to be sewn to the lower boundary darts
+  let mut faces = vec![];
+  let mut firsts = HashMap::new();
+  let mut lasts = HashMap::new();
+  for &(s, l) in &runs {
+    debug_assert!(l + 1 < p, "ring is not hyperbolic");
+    let f = g.add_polygon(p);
+    let u: Vec<Dart> = g.cycle(g[(f, 1)], &[0, 1]).collect();
+    for (i, &y) in u.iter().enumerate().take(l) {
+      g.sew(2, xs[(s + i) % len], y).unwrap();
+    }
+    firsts.insert(s, g[(u[0], 1)]);
+    lasts.insert((s + l) % len, u[l]);
+    faces.push(f);
+  }
+
+  // faces meeting the boundary at a single vertex, between consecutive runs
+  let mut ring = vec![];
+  for (&(s, _), &f) in runs.iter().zip(&faces) {
+    let mut prev = lasts[&s];
+    for _ in 2..missing[s] {
+      let v = g.add_polygon(p);
+      let u = g[(v, 1)];
+      g.sew(2, prev, g[(u, 1)]).unwrap();
+      prev = u;
+      ring.push(v);
+    }
+    g.sew(2, prev, firsts[&s]).unwrap();
+    ring.push(f);
+  }
+  ring
+}
+
+type Complex = (f64, f64);
+
+fn sub(a: Complex, b: Complex) -> Complex {
+  (a.0 - b.0, a.1 - b.1)
+}
+
+fn mul(a: Complex, b: Complex) -> Complex {
+  (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+fn div(a: Complex, b: Complex) -> Complex {
+  let n = b.0 * b.0 + b.1 * b.1;
+  ((a.0 * b.0 + a.1 * b.1) / n, (a.1 * b.0 - a.0 * b.1) / n)
+}
+
+/// The isometry of the Poincaré disk moving c to the origin, applied to z.
+fn to_origin(z: Complex, c: Complex) -> Complex {
+  div(sub(z, c), sub((1., 0.), mul((c.0, -c.1), z)))
+}
+
+/// Hyperbolic rotation of z about c by angle t, in the Poincaré disk.
+fn rotate(z: Complex, c: Complex, t: f64) -> Complex {
+  let w = mul(to_origin(z, c), (t.cos(), t.sin()));
+  to_origin(w, (-c.0, -c.1))
+}
+
+/// Position of each vertex of a {p, q} tiling in the Poincaré disk,
+/// with the polygon at the dart center centered on the origin.
+fn poincare_layout(g: &GMap, p: usize, q: usize, center: Dart) -> OrbitMap<(f64, f64)> {
+  let angle = |k: usize| 2. * PI * k as f64;
+  let circumradius = ((PI / p as f64).tan() * (PI / q as f64).tan())
+    .recip()
+    .acosh();
+  let r = (circumradius / 2.).tanh();
+  let corner = |k: usize| {
+    let t = angle(k) / p as f64;
+    (r * t.cos(), r * t.sin())
+  };
+
+  // the position of each dart's vertex, that of the other end of its edge,
+  // and whether its face is to the left going along the edge
+  let mut states = HashMap::new();
+  states.insert(center, (corner(0), corner(1), true));
+  let mut queue = VecDeque::from([center]);
+  while let Some(d) = queue.pop_front() {
+    let (a, b, left) = states[&d];
+    let t = if left { angle(1) } else { -angle(1) } / q as f64;
+    for (e, state) in [
+      (g[(d, 0)], (b, a, !left)),
+      (g[(d, 1)], (a, rotate(b, a, t), !left)),
+      (g[(d, 2)], (a, b, !left)),
+    ] {
+      states.entry(e).or_insert_with(|| {
+        queue.push_back(e);
+        state
+      });
+    }
+  }
+
+  let mut layout = OrbitMap::new(Alphas::VERTEX);
+  for v in g.one_dart_per_incident_orbit(center, Alphas::VERTEX, Alphas(!0)) {
+    layout.insert(g, v, states[&v].0);
+  }
+  layout
+}
+
+/// The regular map of type {p, q} in which each of the given words of alpha indices
+/// leads from every dart back to itself, if it has at most max_darts darts.
+/// Its darts correspond to the elements of the group generated by reflections r_0, r_1, r_2
+/// with (r_0 r_1)^p = (r_1 r_2)^q = (r_0 r_2)^2 = 1 and the given relations,
+/// which are enumerated by the Todd-Coxeter procedure.
+///
+/// For example, with no relations {3, 5} gives the icosahedron,
+/// and {7, 3} with [0, 1, 2] repeated 8 times gives the Klein quartic.
+pub fn quotient(p: usize, q: usize, relations: &[Vec<usize>], max_darts: usize) -> Option<GMap> {
+  let mut relators: Vec<Vec<usize>> = vec![[0, 1].repeat(p), [1, 2].repeat(q), [0, 2].repeat(2)];
+  relators.extend(relations.iter().cloned());
+  let mut e = Enumeration {
+    table: vec![[None; 3]],
+    parent: vec![0],
+  };
+
+  let mut c = 0;
+  while c < e.table.len() {
+    for w in &relators {
+      if e.live(c) {
+        e.scan_and_fill(c, w);
+      }
+    }
+    for i in 0..3 {
+      if e.live(c) && e.table[c][i].is_none() {
+        e.define(c, i);
+      }
+    }
+    if (0..e.table.len()).filter(|&x| e.live(x)).count() > max_darts {
+      return None;
+    }
+    c += 1;
+  }
+
+  let live: Vec<usize> = (0..e.table.len()).filter(|&x| e.live(x)).collect();
+  let number: HashMap<usize, Dart> = live
+    .iter()
+    .enumerate()
+    .map(|(k, &x)| (x, Dart(k)))
+    .collect();
+  let mut g = GMap::empty(2).unwrap();
+  for _ in &live {
+    g.add_dart();
+  }
+  for &x in &live {
+    for i in 0..3 {
+      let y = e.find(e.table[x][i].unwrap());
+      *g.al1(number[&x], i) = number[&y];
+    }
+  }
+  debug_assert!(g.check_valid().is_ok());
+  Some(g)
+}
+
+/// Coset table for Todd-Coxeter enumeration with involutive generators.
+struct Enumeration {
+  table: Vec<[Option<usize>; 3]>,
+  /// Union-find forest of cosets found to coincide
+  parent: Vec<usize>,
+}
+
+impl Enumeration {
+  fn live(&self, c: usize) -> bool {
+    self.parent[c] == c
+  }
+
+  fn find(&self, mut c: usize) -> usize {
+    while self.parent[c] != c {
+      c = self.parent[c];
+    }
+    c
+  }
+
+  fn define(&mut self, c: usize, i: usize) -> usize {
+    let d = self.table.len();
+    self.table.push([None; 3]);
+    self.parent.push(d);
+    self.table[c][i] = Some(d);
+    self.table[d][i] = Some(c);
+    d
+  }
+
+  /// Trace w from c in both directions, defining cosets to complete it
+  /// and identifying cosets where the two ends disagree.
+  fn scan_and_fill(&mut self, c: usize, w: &[usize]) {
+    let (mut f, mut b) = (c, c);
+    let (mut i, mut j) = (0, w.len());
+    loop {
+      while i < j {
+        match self.table[f][w[i]] {
+          Some(x) => f = x,
+          None => break,
+        }
+        i += 1;
+      }
+      if i == j {
+        self.coincidence(f, b);
+        return;
+      }
+      while j > i {
+        match self.table[b][w[j - 1]] {
+          Some(x) => b = x,
+          None => break,
+        }
+        j -= 1;
+      }
+      if i == j {
+        self.coincidence(f, b);
+        return;
+      }
+      if j == i + 1 {
+        self.table[f][w[i]] = Some(b);
+        self.table[b][w[i]] = Some(f);
+        return;
+      }
+      self.define(f, w[i]);
+    }
+  }
+
+  fn coincidence(&mut self, a: usize, b: usize) {
+    let mut queue = VecDeque::new();
+    self.merge(a, b, &mut queue);
+    while let Some(e) = queue.pop_front() {
+      for i in 0..3 {
+        if let Some(f) = self.table[e][i] {
+          self.table[f][i] = None;
+          let (e1, f1) = (self.find(e), self.find(f));
+          if let Some(x) = self.table[e1][i] {
+            self.merge(f1, x, &mut queue);
+          } else if let Some(x) = self.table[f1][i] {
+            self.merge(e1, x, &mut queue);
+          } else {
+            self.table[e1][i] = Some(f1);
+            self.table[f1][i] = Some(e1);
+          }
+        }
+      }
+    }
+  }
+
+  fn merge(&mut self, a: usize, b: usize, queue: &mut VecDeque<usize>) {
+    let (a, b) = (self.find(a), self.find(b));
+    if a != b {
+      let (a, b) = (a.min(b), a.max(b));
+      self.parent[b] = a;
+      queue.push_back(b);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::topology::Surface;
+
+  #[test]
+  fn test_rings() {
+    for (p, q) in [(7, 3), (4, 5), (3, 7), (5, 4)] {
+      let (g, rings, layout) = rings(p, q, 3).unwrap();
+      assert_eq!(rings[1].len(), p * (q - 2));
+      let c = topology::components(&g).unwrap();
+      assert_eq!(c.len(), 1);
+      assert_eq!(c[0].surface(), Some(Surface::DISK));
+      assert!(g.is_oriented());
+      assert_eq!(
+        rings.iter().map(Vec::len).sum::<usize>(),
+        g.one_dart_per_cell(2).count()
+      );
+      assert!(g
+        .one_dart_per_cell(2)
+        .all(|f| g.cycle(f, &[0, 1]).count() == p));
+      // vertices of all but the last ring are complete
+      for &f in rings[..3].iter().flatten() {
+        for v in g.cycle(f, &[0, 1]) {
+          assert_eq!(g.cell(v, 0).count(), 2 * q);
+        }
+      }
+
+      // all edges have the same hyperbolic length
+      let length = 2. * ((PI / p as f64).cos() / (PI / q as f64).sin()).acosh();
+      for d in g.darts() {
+        let (a, b) = (layout.map()[&d], layout.map()[&g[(d, 0)]]);
+        assert!(a.0.hypot(a.1) < 1.);
+        let z = to_origin(b, a);
+        let l = 2. * z.0.hypot(z.1).atanh();
+        assert!((l - length).abs() < 1e-6, "{} {}", l, length);
+      }
+    }
+    for (p, q) in [(4, 4), (6, 3), (3, 6), (3, 3), (2, 9)] {
+      assert!(rings(p, q, 1).is_none());
+    }
+  }
+
+  #[test]
+  fn test_quotient() {
+    let g = quotient(3, 5, &[], 1000).unwrap();
+    assert_eq!(g.darts().count(), 120);
+    let c = topology::components(&g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::SPHERE));
+    assert_eq!(c[0].faces, 20);
+
+    let g = quotient(7, 3, &[[0, 1, 2].repeat(8)], 1000).unwrap();
+    assert_eq!(g.darts().count(), 336);
+    let c = topology::components(&g).unwrap();
+    assert_eq!(
+      c[0].surface(),
+      Some(Surface::Orientable {
+        genus: 3,
+        boundaries: 0
+      })
+    );
+    assert!(g
+      .one_dart_per_cell(2)
+      .all(|f| g.cycle(f, &[0, 1]).count() == 7));
+    assert!(g.one_dart_per_cell(0).all(|v| g.cell(v, 0).count() == 6));
+
+    // the torus {4, 4}_(2, 0)
+    let g = quotient(4, 4, &[[0, 1, 2, 1].repeat(2)], 1000).unwrap();
+    let c = topology::components(&g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::TORUS));
+
+    assert!(quotient(7, 3, &[], 1000).is_none());
+  }
+}
@@ -1,2 +1,224 @@
 pub mod square;
 pub mod hex;
+pub mod hyperbolic;
+pub mod periodic;
+pub mod polyhedron;
+pub mod triangle;
+pub mod uniform;
+
+use crate::{Alphas, Dart, GMap, GMapLike};
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Dart of an implicit grid: the cell at (row, col), and the index of the dart within that cell.
+/// Darts of a cell with k sides are numbered 0..2k clockwise, two per side,
+/// so that alpha_0 pairs 2j with 2j+1.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GridDart {
+  pub row: usize,
+  pub col: usize,
+  pub i: usize,
+}
+
+/// Build the cells of an implicit grid where the mask is true, sewn together wherever they are adjacent.
+/// Rows of the mask may have different lengths, with missing entries counting as false.
+/// Returns the dart of each cell corresponding to its dart 0, or None for absent cells.
+fn masked<G>(grid: &G, mask: &[Vec<bool>]) -> (GMap, Vec<Vec<Option<Dart>>>)
+where
+  G: GMapLike<Dart = GridDart>,
+{
+  let present = |d: &GridDart| mask[d.row].get(d.col).cloned().unwrap_or(false);
+  let (g, m) = GMap::induced(grid, grid.darts().filter(present));
+  let cells = mask
+    .iter()
+    .enumerate()
+    .map(|(row, cols)| {
+      (0..cols.len())
+        .map(|col| m.get(&GridDart { row, col, i: 0 }).cloned())
+        .collect()
+    })
+    .collect();
+  (g, cells)
+}
+
+/// Mask with the given cells present, just large enough to hold them.
+fn mask_of(cells: &[(usize, usize)]) -> Vec<Vec<bool>> {
+  let n = cells.iter().map(|&(r, _)| r + 1).max().unwrap_or(0);
+  let m = cells.iter().map(|&(_, c)| c + 1).max().unwrap_or(0);
+  let mut mask = vec![vec![false; m]; n];
+  for &(r, c) in cells {
+    mask[r][c] = true;
+  }
+  mask
+}
+
+/// Coordinates of the vertices, edges and faces of a grid, with lookups in both directions.
+/// Faces are at (row, col), and vertices and edges are at the locations given by `v_loc` and `e_loc2`
+/// of the corresponding implicit grid, so that each edge is at the sum of its endpoints.
+///
+/// Each dart has the coordinates of its cells as seen from its own face,
+/// so in a wrapped grid a cell on a seam has several coordinates, all of which lead back to it.
+#[derive(Debug, Clone)]
+pub struct GridCoords {
+  /// Coordinates of the vertex, edge and face of each dart
+  coords: HashMap<Dart, [(isize, isize); 3]>,
+  /// Lowest dart with each coordinates, for vertices, edges and faces
+  cells: [HashMap<(isize, isize), Dart>; 3],
+  /// Lowest dart with each coordinates of its vertex and edge
+  half_edges: HashMap<[(isize, isize); 2], Dart>,
+}
+
+impl GridCoords {
+  /// Coordinates of a grid of k-gons, given the dart of each face corresponding to dart 0 of an implicit grid,
+  /// and the coordinates of the vertex, edge and face of each implicit dart.
+  fn new<L>(g: &GMap, rows: &[Vec<Dart>], k: usize, locate: L) -> Self
+  where
+    L: Fn(GridDart) -> [(isize, isize); 3],
+  {
+    let mut coords = HashMap::new();
+    for (row, darts) in rows.iter().enumerate() {
+      for (col, &f) in darts.iter().enumerate() {
+        for (i, d) in g.cycle(f, &[0, 1]).flat_map(|d| [d, g[(d, 0)]]).enumerate() {
+          coords.insert(d, locate(GridDart { row, col, i }));
+        }
+        debug_assert_eq!(g.cycle(f, &[0, 1]).count(), k);
+      }
+    }
+    let mut cells: [HashMap<(isize, isize), Dart>; 3] = Default::default();
+    let mut half_edges: HashMap<[(isize, isize); 2], Dart> = HashMap::new();
+    for (&d, &[v, e, f]) in &coords {
+      for (cell, p) in cells.iter_mut().zip([v, e, f]) {
+        let x = cell.entry(p).or_insert(d);
+        *x = d.min(*x);
+      }
+      let x = half_edges.entry([v, e]).or_insert(d);
+      *x = d.min(*x);
+    }
+    Self {
+      coords,
+      cells,
+      half_edges,
+    }
+  }
+
+  /// Coordinates of the cell of d given by `Alphas::VERTEX`, `Alphas::EDGE` or `Alphas::FACE`
+  pub fn coords_of(&self, d: Dart, a: Alphas) -> Option<(isize, isize)> {
+    let i = cell_dimension(a)?;
+    self.coords.get(&d).map(|c| c[i])
+  }
+
+  /// A dart of the cell at p given by `Alphas::VERTEX`, `Alphas::EDGE` or `Alphas::FACE`
+  pub fn dart_at(&self, a: Alphas, p: (isize, isize)) -> Option<Dart> {
+    self.cells[cell_dimension(a)?].get(&p).cloned()
+  }
+
+  /// Dart of the face at (r, c), which is the face's dart in the grid's table of faces
+  pub fn face_at(&self, r: isize, c: isize) -> Option<Dart> {
+    self.dart_at(Alphas::FACE, (r, c))
+  }
+
+  /// A dart of the vertex at p
+  pub fn vertex_at(&self, p: (isize, isize)) -> Option<Dart> {
+    self.dart_at(Alphas::VERTEX, p)
+  }
+
+  /// A dart of the edge at p, in doubled coordinates
+  pub fn edge_at2(&self, p: (isize, isize)) -> Option<Dart> {
+    self.dart_at(Alphas::EDGE, p)
+  }
+
+  /// A dart at vertex v0 of the edge from v0 to v1, if they are adjacent
+  pub fn edge_between(&self, v0: (isize, isize), v1: (isize, isize)) -> Option<Dart> {
+    self
+      .half_edges
+      .get(&[v0, (v0.0 + v1.0, v0.1 + v1.1)])
+      .cloned()
+  }
+}
+
+/// Dimension of the cells given by a, if they are vertices, edges or faces
+fn cell_dimension(a: Alphas) -> Option<usize> {
+  (0..3).find(|&i| Alphas::cell(i) == a)
+}
+
+/// How a pair of opposite sides of a grid are joined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wrap {
+  /// Left as boundary
+  None,
+  /// Glued in the same direction, as for a cylinder
+  Straight,
+  /// Glued in opposite directions, as for a Möbius strip
+  Twisted,
+}
+
+/// Glue two sides of a grid, given as paths in the same direction around its boundary.
+fn glue_sides(g: &mut GMap, side0: &[Dart], side1: &[Dart], wrap: Wrap) {
+  match wrap {
+    Wrap::None => {}
+    // going around the boundary, opposite sides run in opposite directions
+    Wrap::Straight => {
+      g.glue(side0, side1, true).unwrap();
+    }
+    Wrap::Twisted => {
+      g.glue(side0, side1, false).unwrap();
+    }
+  }
+}
+
+/// Build a 2-map from polygons given by their vertices, all going around in the same direction,
+/// sewing polygons along each edge which they traverse in opposite directions.
+/// Returns the dart of each polygon at its first vertex, and the vertex of every dart.
+fn from_polygons<V: Copy + Eq + Hash>(faces: &[Vec<V>]) -> (GMap, Vec<Dart>, HashMap<Dart, V>) {
+  let mut g = GMap::empty(2).unwrap();
+  let mut vertices = HashMap::new();
+  // the dart at p on each edge from p to q
+  let mut edges: HashMap<(V, V), Dart> = HashMap::new();
+  let mut darts = vec![];
+  for corners in faces {
+    let f = g.add_polygon(corners.len());
+    darts.push(f);
+    for (j, x) in g
+      .cycle(f, &[0, 1])
+      .collect::<Vec<_>>()
+      .into_iter()
+      .enumerate()
+    {
+      let (p, q) = (corners[j], corners[(j + 1) % corners.len()]);
+      vertices.insert(x, p);
+      vertices.insert(g[(x, 0)], q);
+      let old = edges.insert((p, q), x);
+      debug_assert!(old.is_none(), "overlapping polygons");
+    }
+  }
+  for (&(p, q), &x) in &edges {
+    if let Some(&y) = edges.get(&(q, p)) {
+      if g.is_free(x, 2) {
+        g.sew(2, x, g[(y, 0)]).unwrap();
+      }
+    }
+  }
+  (g, darts, vertices)
+}
+
+/// alpha_0 and alpha_1 of a dart within a k-sided cell, numbered as in `GridDart`.
+fn polygon_alpha(k: usize, i: usize, j: usize) -> usize {
+  match (j, i % 2) {
+    (0, _) => i ^ 1,
+    (1, 0) => (i + 2 * k - 1) % (2 * k),
+    (1, _) => (i + 1) % (2 * k),
+    _ => unreachable!(),
+  }
+}
+
+/// Index of alpha_2 of dart i within the neighboring cell,
+/// for grids of centrally symmetric k-gons where side j is glued to the neighbor's side j + k/2.
+fn opposite_side(k: usize, i: usize) -> usize {
+  let side = (i / 2 + k / 2) % k;
+  2 * side + (1 - i % 2)
+}
@@ -0,0 +1,212 @@
+use crate::{Dart, GMap, GMapError, GMapLike, OrbitMap};
+
+use std::collections::HashMap;
+
+/// Sewing of a boundary edge of a translation unit to the copy of another one in a neighboring unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pairing {
+  pub dart: Dart,
+  /// Dart sewn to `dart` by alpha_2, at the same vertex.
+  /// alpha_0 of the two darts are sewn as well.
+  pub other: Dart,
+  /// (rows, columns) from the unit containing `dart` to the one containing `other`
+  pub offset: (isize, isize),
+}
+
+/// One translation unit of a periodic tiling of the plane.
+#[derive(Clone)]
+pub struct TranslationUnit {
+  /// The faces of the unit, sewn together where they meet within the unit
+  pub g: GMap,
+  /// Position of each vertex of g
+  pub layout: OrbitMap<(f64, f64)>,
+  /// Translation from each row of units to the next, and from each column to the next
+  pub translations: [(f64, f64); 2],
+  /// How the units are sewn together, with one pairing for each edge of the boundary
+  pub pairings: Vec<Pairing>,
+}
+
+/// Largest offset between units considered by `TranslationUnit::from_layout`
+const MAX_OFFSET: isize = 2;
+
+impl TranslationUnit {
+  /// Unit whose pairings are found from the layout,
+  /// by matching each 2-free edge with one that lies on it when translated by up to two units.
+  pub fn from_layout(g: GMap, layout: OrbitMap<(f64, f64)>, translations: [(f64, f64); 2]) -> Self {
+    let position = |d: Dart| layout.map()[&d];
+    let close = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).hypot(a.1 - b.1) < 1e-6;
+    let free: Vec<Dart> = g.darts().filter(|&d| g.is_free(d, 2)).collect();
+    let mut pairings = vec![];
+    for &dart in &free {
+      let (p, q) = (position(dart), position(g[(dart, 0)]));
+      for dr in -MAX_OFFSET..=MAX_OFFSET {
+        for dc in -MAX_OFFSET..=MAX_OFFSET {
+          let t = translate((0., 0.), translations, (-dr, -dc));
+          let other = free.iter().find(|&&e| {
+            e != dart
+              && close(position(e), (p.0 + t.0, p.1 + t.1))
+              && close(position(g[(e, 0)]), (q.0 + t.0, q.1 + t.1))
+          });
+          // each edge is sewn once, from its lowest dart
+          if let Some(&other) = other {
+            if dart < g[(dart, 0)] && dart < other && dart < g[(other, 0)] {
+              pairings.push(Pairing {
+                dart,
+                other,
+                offset: (dr, dc),
+              });
+            }
+          }
+        }
+      }
+    }
+    Self {
+      g,
+      layout,
+      translations,
+      pairings,
+    }
+  }
+}
+
+fn translate(p: (f64, f64), translations: [(f64, f64); 2], (r, c): (isize, isize)) -> (f64, f64) {
+  let [(rx, ry), (cx, cy)] = translations;
+  let (r, c) = (r as f64, c as f64);
+  (p.0 + r * rx + c * cx, p.1 + r * ry + c * cy)
+}
+
+/// A patch of a periodic tiling.
+#[derive(Clone)]
+pub struct Patch {
+  pub g: GMap,
+  /// Mapping from darts of the translation unit to darts of g, for each unit by row and column
+  pub units: Vec<Vec<HashMap<Dart, Dart>>>,
+  /// Position of each dart's vertex, in the coordinates of the translation unit.
+  /// This is per dart rather than per vertex: when wrapping, a vertex on the seam has the positions
+  /// of its darts within their own units, so that each face is drawn in one piece.
+  pub layout: HashMap<Dart, (f64, f64)>,
+}
+
+/// n rows of m copies of a translation unit, sewn together according to its pairings.
+/// wrap_rows joins the last row to the first, and wrap_cols the last column to the first,
+/// so that wrapping both gives a torus.
+pub fn patch(
+  unit: &TranslationUnit,
+  n: usize,
+  m: usize,
+  wrap_rows: bool,
+  wrap_cols: bool,
+) -> Result<Patch, GMapError> {
+  if unit.g.dimension() != 2 {
+    return Err(GMapError::WrongDimension(2));
+  }
+  for p in &unit.pairings {
+    if !unit.g.is_free(p.dart, 2) || !unit.g.is_free(p.other, 2) {
+      return Err(GMapError::NotFree);
+    }
+  }
+
+  let mut g = GMap::empty(2)?;
+  let mut units = vec![];
+  let mut layout = HashMap::new();
+  for r in 0..n {
+    let mut row = vec![];
+    for c in 0..m {
+      let copy = g.disjoint_union(&unit.g)?;
+      for (&d, &x) in &copy {
+        let p = translate(
+          unit.layout.map()[&d],
+          unit.translations,
+          (r as isize, c as isize),
+        );
+        layout.insert(x, p);
+      }
+      row.push(copy);
+    }
+    units.push(row);
+  }
+
+  let neighbor = |k: usize, offset: isize, len: usize, wrap: bool| {
+    let j = k as isize + offset;
+    if wrap {
+      Some(j.rem_euclid(len as isize) as usize)
+    } else {
+      (0..len as isize).contains(&j).then_some(j as usize)
+    }
+  };
+  for r in 0..n {
+    for c in 0..m {
+      for p in &unit.pairings {
+        let (dr, dc) = p.offset;
+        if let (Some(r1), Some(c1)) = (neighbor(r, dr, n, wrap_rows), neighbor(c, dc, m, wrap_cols))
+        {
+          g.sew(2, units[r][c][&p.dart], units[r1][c1][&p.other])?;
+        }
+      }
+    }
+  }
+
+  Ok(Patch { g, units, layout })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::{square, Wrap};
+  use crate::isomorphism::is_isomorphic;
+  use crate::topology::{self, Surface};
+  use crate::Alphas;
+
+  fn square_unit() -> TranslationUnit {
+    let mut g = GMap::empty(2).unwrap();
+    let s = g.add_polygon(4);
+    let mut layout = OrbitMap::new(Alphas::VERTEX);
+    for (d, p) in g
+      .cycle(s, &[0, 1])
+      .zip([(0., 0.), (1., 0.), (1., 1.), (0., 1.)])
+    {
+      layout.insert(&g, d, p);
+    }
+    TranslationUnit::from_layout(g, layout, [(0., 1.), (1., 0.)])
+  }
+
+  #[test]
+  fn test_patch() {
+    let unit = square_unit();
+    assert_eq!(unit.pairings.len(), 2);
+
+    let p = patch(&unit, 2, 3, false, false).unwrap();
+    assert!(is_isomorphic(&p.g, &square::new(2, 3).0));
+    assert_eq!(p.units.len(), 2);
+    assert_eq!(p.units[1].len(), 3);
+    let s = p.units[1][2][&unit.g.darts().min().unwrap()];
+    assert_eq!(p.layout[&s], (2., 1.));
+    for d in p.g.darts() {
+      if !p.g.is_free(d, 2) {
+        assert_eq!(p.layout[&d], p.layout[&p.g[(d, 2)]]);
+      }
+    }
+
+    let p = patch(&unit, 2, 3, true, true).unwrap();
+    assert!(is_isomorphic(
+      &p.g,
+      &square::wrapped(2, 3, Wrap::Straight, Wrap::Straight).0
+    ));
+    let c = topology::components(&p.g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::TORUS));
+    // the corner vertex is on both seams, with a different position in each unit around it
+    let v = p.units[0][0][&unit.g.darts().min().unwrap()];
+    let corners: Vec<(f64, f64)> = p.g.cell(v, 0).map(|x| p.layout[&x]).collect();
+    for corner in [(0., 0.), (3., 0.), (0., 2.), (3., 2.)] {
+      assert!(corners.contains(&corner));
+    }
+
+    let p = patch(&unit, 2, 3, false, true).unwrap();
+    let c = topology::components(&p.g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::CYLINDER));
+
+    let mut bad = square_unit();
+    bad.pairings.push(bad.pairings[0]);
+    assert!(patch(&bad, 2, 2, false, false).is_err());
+  }
+}
@@ -0,0 +1,411 @@
+use super::from_polygons;
+use crate::{Alphas, Dart, GMap, GMapLike, OrbitMap};
+
+use std::f64::consts::PI;
+
+/// Convex polyhedra with regular faces and all vertices alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Solid {
+  Tetrahedron,
+  Cube,
+  Octahedron,
+  Dodecahedron,
+  Icosahedron,
+  TruncatedTetrahedron,
+  Cuboctahedron,
+  TruncatedCube,
+  TruncatedOctahedron,
+  Rhombicuboctahedron,
+  TruncatedCuboctahedron,
+  SnubCube,
+  Icosidodecahedron,
+  TruncatedDodecahedron,
+  TruncatedIcosahedron,
+  Rhombicosidodecahedron,
+  TruncatedIcosidodecahedron,
+  SnubDodecahedron,
+  /// Prism over an n-gon, for n >= 3
+  Prism(usize),
+  /// Antiprism over an n-gon, for n >= 3
+  Antiprism(usize),
+}
+
+impl Solid {
+  pub const PLATONIC: [Solid; 5] = [
+    Solid::Tetrahedron,
+    Solid::Cube,
+    Solid::Octahedron,
+    Solid::Dodecahedron,
+    Solid::Icosahedron,
+  ];
+
+  pub const ARCHIMEDEAN: [Solid; 13] = [
+    Solid::TruncatedTetrahedron,
+    Solid::Cuboctahedron,
+    Solid::TruncatedCube,
+    Solid::TruncatedOctahedron,
+    Solid::Rhombicuboctahedron,
+    Solid::TruncatedCuboctahedron,
+    Solid::SnubCube,
+    Solid::Icosidodecahedron,
+    Solid::TruncatedDodecahedron,
+    Solid::TruncatedIcosahedron,
+    Solid::Rhombicosidodecahedron,
+    Solid::TruncatedIcosidodecahedron,
+    Solid::SnubDodecahedron,
+  ];
+
+  /// Wythoff construction of the solid: the symmetry group of the regular polyhedron {p, q},
+  /// and which of its mirrors the generating vertex lies off, or None for a snub.
+  fn wythoff(self) -> Option<(usize, usize, Option<&'static [usize]>)> {
+    Some(match self {
+      Solid::Tetrahedron => (3, 3, Some(&[0])),
+      Solid::Cube => (4, 3, Some(&[0])),
+      Solid::Octahedron => (3, 4, Some(&[0])),
+      Solid::Dodecahedron => (5, 3, Some(&[0])),
+      Solid::Icosahedron => (3, 5, Some(&[0])),
+      Solid::TruncatedTetrahedron => (3, 3, Some(&[0, 1])),
+      Solid::Cuboctahedron => (4, 3, Some(&[1])),
+      Solid::TruncatedCube => (4, 3, Some(&[0, 1])),
+      Solid::TruncatedOctahedron => (3, 4, Some(&[0, 1])),
+      Solid::Rhombicuboctahedron => (4, 3, Some(&[0, 2])),
+      Solid::TruncatedCuboctahedron => (4, 3, Some(&[0, 1, 2])),
+      Solid::SnubCube => (4, 3, None),
+      Solid::Icosidodecahedron => (5, 3, Some(&[1])),
+      Solid::TruncatedDodecahedron => (5, 3, Some(&[0, 1])),
+      Solid::TruncatedIcosahedron => (3, 5, Some(&[0, 1])),
+      Solid::Rhombicosidodecahedron => (5, 3, Some(&[0, 2])),
+      Solid::TruncatedIcosidodecahedron => (5, 3, Some(&[0, 1, 2])),
+      Solid::SnubDodecahedron => (5, 3, None),
+      Solid::Prism(_) | Solid::Antiprism(_) => return None,
+    })
+  }
+}
+
+/// A polyhedron as a closed 2-map.
+#[derive(Clone)]
+pub struct Polyhedron {
+  pub g: GMap,
+  /// One dart of each face
+  pub faces: Vec<Dart>,
+  /// Position of each vertex in space, with unit edge lengths and centered on the origin
+  pub positions: OrbitMap<(f64, f64, f64)>,
+  /// Position of each vertex in the plane by stereographic projection,
+  /// from the center of a largest face, which becomes the outside of the drawing
+  pub layout: OrbitMap<(f64, f64)>,
+}
+
+type Vector = [f64; 3];
+
+fn dot(a: Vector, b: Vector) -> f64 {
+  a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vector, b: Vector) -> Vector {
+  [
+    a[1] * b[2] - a[2] * b[1],
+    a[2] * b[0] - a[0] * b[2],
+    a[0] * b[1] - a[1] * b[0],
+  ]
+}
+
+fn scale(a: Vector, x: f64) -> Vector {
+  a.map(|c| c * x)
+}
+
+fn sub(a: Vector, b: Vector) -> Vector {
+  [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(a: Vector) -> f64 {
+  dot(a, a).sqrt()
+}
+
+fn normalize(a: Vector) -> Vector {
+  scale(a, 1. / norm(a))
+}
+
+/// The mirrors of the symmetry group of {p, q}, as unit normals pointing into the fundamental triangle.
+/// The rotations r_0 r_1, r_1 r_2 and r_0 r_2 are about the center of a face, a vertex and an edge.
+fn mirrors(p: usize, q: usize) -> [Vector; 3] {
+  let (a, b) = (PI / p as f64, PI / q as f64);
+  let y = -b.cos() / a.sin();
+  [
+    [1., 0., 0.],
+    [-a.cos(), a.sin(), 0.],
+    [0., y, (1. - y * y).sqrt()],
+  ]
+}
+
+fn reflect(x: Vector, n: Vector) -> Vector {
+  sub(x, scale(n, 2. * dot(x, n)))
+}
+
+/// The point of the sphere at the given distances from the mirrors, up to scale.
+fn chamber_point(n: [Vector; 3], b: Vector) -> Vector {
+  let c = [cross(n[1], n[2]), cross(n[2], n[0]), cross(n[0], n[1])];
+  normalize((0..3).fold([0.; 3], |w, i| {
+    let v = scale(c[i], b[i]);
+    [w[0] + v[0], w[1] + v[1], w[2] + v[2]]
+  }))
+}
+
+/// The images of w under the group generated by the given maps.
+fn orbit(w: Vector, generators: &[&dyn Fn(Vector) -> Vector]) -> Vec<Vector> {
+  let mut points = vec![w];
+  let mut k = 0;
+  while k < points.len() {
+    for f in generators {
+      let x = f(points[k]);
+      if points.iter().all(|&y| norm(sub(x, y)) > 1e-6) {
+        points.push(x);
+      }
+    }
+    k += 1;
+  }
+  points
+}
+
+/// Vertices of the solid, in no particular order.
+fn vertices(s: Solid) -> Vec<Vector> {
+  let ring = |n: usize, r: f64, z: f64, t: f64| -> Vec<Vector> {
+    (0..n)
+      .map(|k| {
+        let a = 2. * PI * k as f64 / n as f64 + t;
+        [r * a.cos(), r * a.sin(), z]
+      })
+      .collect()
+  };
+  match (s, s.wythoff()) {
+    (Solid::Prism(n), _) => {
+      let r = 1. / (2. * (PI / n as f64).sin());
+      [ring(n, r, 0.5, 0.), ring(n, r, -0.5, 0.)].concat()
+    }
+    (Solid::Antiprism(n), _) => {
+      let r = 1. / (2. * (PI / n as f64).sin());
+      let d = 2. * r * (PI / (2 * n) as f64).sin();
+      let h = (1. - d * d).sqrt();
+      [ring(n, r, h / 2., 0.), ring(n, r, -h / 2., PI / n as f64)].concat()
+    }
+    (_, Some((p, q, Some(off)))) => {
+      let n = mirrors(p, q);
+      let b = [0, 1, 2].map(|i| off.contains(&i) as usize as f64);
+      let r = |i: usize| move |x: Vector| reflect(x, n[i]);
+      orbit(chamber_point(n, b), &[&r(0), &r(1), &r(2)])
+    }
+    (_, Some((p, q, None))) => {
+      let n = mirrors(p, q);
+      let rotation = |i: usize, j: usize| move |x: Vector| reflect(reflect(x, n[j]), n[i]);
+      let (face, vertex, edge) = (rotation(0, 1), rotation(1, 2), rotation(0, 2));
+      // a snub vertex is equally far from its images under the three rotations
+      let f = |b: [f64; 2]| {
+        let w = chamber_point(n, [b[0], 1., b[1]]);
+        let d = [face(w), vertex(w), edge(w)].map(|x| norm(sub(x, w)));
+        [d[0] - d[1], d[1] - d[2]]
+      };
+      let mut b = [1., 1.];
+      for _ in 0..100 {
+        let y = f(b);
+        if y[0].abs().max(y[1].abs()) < 1e-14 {
+          break;
+        }
+        let h = 1e-7;
+        let j0 = f([b[0] + h, b[1]]).map(|c| c / h);
+        let j1 = f([b[0], b[1] + h]).map(|c| c / h);
+        let j = [
+          [j0[0] - y[0] / h, j1[0] - y[0] / h],
+          [j0[1] - y[1] / h, j1[1] - y[1] / h],
+        ];
+        let det = j[0][0] * j[1][1] - j[0][1] * j[1][0];
+        b[0] -= (y[0] * j[1][1] - y[1] * j[0][1]) / det;
+        b[1] -= (j[0][0] * y[1] - j[1][0] * y[0]) / det;
+      }
+      orbit(chamber_point(n, [b[0], 1., b[1]]), &[&face, &vertex])
+    }
+    (_, None) => unreachable!(),
+  }
+}
+
+/// Build one of the solids, with its faces found as the cycles of its shortest edges
+/// around each vertex.
+/// Returns None for prisms and antiprisms over fewer than 3 sides.
+pub fn polyhedron(s: Solid) -> Option<Polyhedron> {
+  if let Solid::Prism(n) | Solid::Antiprism(n) = s {
+    if n < 3 {
+      return None;
+    }
+  }
+  let points = vertices(s);
+  let center = scale(
+    points
+      .iter()
+      .fold([0.; 3], |c, &x| [c[0] + x[0], c[1] + x[1], c[2] + x[2]]),
+    1. / points.len() as f64,
+  );
+  let points: Vec<Vector> = points.iter().map(|&x| sub(x, center)).collect();
+  let distance = |i: usize, j: usize| norm(sub(points[i], points[j]));
+  let edge = (0..points.len())
+    .flat_map(|i| (0..i).map(move |j| (i, j)))
+    .map(|(i, j)| distance(i, j))
+    .fold(f64::INFINITY, f64::min);
+  let points: Vec<Vector> = points.iter().map(|&x| scale(x, 1. / edge)).collect();
+
+  // neighbors of each vertex, counterclockwise seen from outside
+  let neighbors: Vec<Vec<usize>> = (0..points.len())
+    .map(|i| {
+      let up = normalize(points[i]);
+      let e0 = normalize(cross(
+        up,
+        if up[0].abs() < 0.9 {
+          [1., 0., 0.]
+        } else {
+          [0., 1., 0.]
+        },
+      ));
+      let e1 = cross(up, e0);
+      let mut ns: Vec<(f64, usize)> = (0..points.len())
+        .filter(|&j| j != i && (norm(sub(points[i], points[j])) - 1.).abs() < 1e-6)
+        .map(|j| {
+          let d = sub(points[j], points[i]);
+          (dot(d, e1).atan2(dot(d, e0)), j)
+        })
+        .collect();
+      ns.sort_by(|a, b| a.0.total_cmp(&b.0));
+      ns.into_iter().map(|(_, j)| j).collect()
+    })
+    .collect();
+
+  // faces counterclockwise from outside, each keeping to the left of its edges
+  let mut seen = std::collections::HashSet::new();
+  let mut faces = vec![];
+  for i in 0..points.len() {
+    for &j in &neighbors[i] {
+      if seen.contains(&(i, j)) {
+        continue;
+      }
+      let mut face = vec![];
+      let (mut a, mut b) = (i, j);
+      while seen.insert((a, b)) {
+        face.push(a);
+        let ns = &neighbors[b];
+        let k = ns.iter().position(|&x| x == a).unwrap();
+        (a, b) = (b, ns[(k + ns.len() - 1) % ns.len()]);
+      }
+      faces.push(face);
+    }
+  }
+
+  let (g, darts, vertex) = from_polygons(&faces);
+  let mut positions = OrbitMap::new(Alphas::VERTEX);
+  for v in g.one_dart_per_cell(0) {
+    let [x, y, z] = points[vertex[&v]];
+    positions.insert(&g, v, (x, y, z));
+  }
+
+  // project from the center of a largest face
+  let largest = (0..faces.len()).max_by_key(|&f| faces[f].len()).unwrap();
+  let pole = normalize(faces[largest].iter().fold([0.; 3], |c, &i| {
+    let x = points[i];
+    [c[0] + x[0], c[1] + x[1], c[2] + x[2]]
+  }));
+  let e0 = normalize(sub(
+    points[faces[largest][0]],
+    scale(pole, dot(points[faces[largest][0]], pole)),
+  ));
+  let e1 = cross(e0, pole);
+  let mut layout = OrbitMap::new(Alphas::VERTEX);
+  for v in g.one_dart_per_cell(0) {
+    let x = normalize(points[vertex[&v]]);
+    let t = 1. - dot(x, pole);
+    layout.insert(&g, v, (dot(x, e0) / t, dot(x, e1) / t));
+  }
+
+  Some(Polyhedron {
+    g,
+    faces: darts,
+    positions,
+    layout,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::hyperbolic;
+  use crate::isomorphism::is_isomorphic;
+  use crate::topology::{self, Surface};
+
+  #[test]
+  fn test_polyhedra() {
+    let counts = [
+      (4, 6, 4),
+      (8, 12, 6),
+      (6, 12, 8),
+      (20, 30, 12),
+      (12, 30, 20),
+      (12, 18, 8),
+      (12, 24, 14),
+      (24, 36, 14),
+      (24, 36, 14),
+      (24, 48, 26),
+      (48, 72, 26),
+      (24, 60, 38),
+      (30, 60, 32),
+      (60, 90, 32),
+      (60, 90, 32),
+      (60, 120, 62),
+      (120, 180, 62),
+      (60, 150, 92),
+      (10, 15, 7),
+      (12, 24, 14),
+    ];
+    let solids = Solid::PLATONIC
+      .into_iter()
+      .chain(Solid::ARCHIMEDEAN)
+      .chain([Solid::Prism(5), Solid::Antiprism(6)]);
+    for (s, (v, e, f)) in solids.zip(counts) {
+      let p = polyhedron(s).unwrap();
+      let c = topology::components(&p.g).unwrap();
+      assert_eq!(c.len(), 1, "{:?}", s);
+      assert_eq!(
+        (c[0].vertices, c[0].edges, c[0].faces),
+        (v, e, f),
+        "{:?}",
+        s
+      );
+      assert_eq!(c[0].surface(), Some(Surface::SPHERE));
+      assert!(p.g.is_oriented());
+      assert_eq!(p.faces.len(), f);
+      for d in p.g.darts() {
+        let (a, b) = (p.positions.map()[&d], p.positions.map()[&p.g[(d, 0)]]);
+        let l = ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)).sqrt();
+        assert!((l - 1.).abs() < 1e-6, "{:?}", s);
+      }
+    }
+
+    for (s, (p, q)) in Solid::PLATONIC
+      .into_iter()
+      .zip([(3, 3), (4, 3), (3, 4), (5, 3), (3, 5)])
+    {
+      let regular = hyperbolic::quotient(p, q, &[], 1000).unwrap();
+      assert!(is_isomorphic(&polyhedron(s).unwrap().g, &regular));
+    }
+    assert!(is_isomorphic(
+      &polyhedron(Solid::Prism(4)).unwrap().g,
+      &polyhedron(Solid::Cube).unwrap().g
+    ));
+    assert!(is_isomorphic(
+      &polyhedron(Solid::Antiprism(3)).unwrap().g,
+      &polyhedron(Solid::Octahedron).unwrap().g
+    ));
+  }
+
+  #[test]
+  fn test_degenerate_prisms() {
+    let c = topology::components(&polyhedron(Solid::Prism(3)).unwrap().g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (6, 9, 5));
+    for s in [Solid::Prism(0), Solid::Prism(2), Solid::Antiprism(2)] {
+      assert!(polyhedron(s).is_none(), "{:?}", s);
+    }
+  }
+}
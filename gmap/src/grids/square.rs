@@ -1,4 +1,7 @@
-use crate::{Dart, GMap};
+use super::{
+  glue_sides, mask_of, masked, opposite_side, polygon_alpha, GridCoords, GridDart, Wrap,
+};
+use crate::{topology, Alphas, Dart, GMap, GMapLike};
 
 use itertools::Itertools;
 
@@ -23,6 +26,38 @@ pub fn new(n: usize, m: usize) -> (GMap, Vec<Vec<Dart>>) {
   (g, rows)
 }
 
+/// n * m grid as for `new`, with the north side joined to the south side according to wrap_rows,
+/// and the west side joined to the east side according to wrap_cols.
+/// For example, wrapping both straight gives a torus,
+/// and wrapping one straight and the other twisted gives a Klein bottle.
+pub fn wrapped(n: usize, m: usize, wrap_rows: Wrap, wrap_cols: Wrap) -> (GMap, Vec<Vec<Dart>>) {
+  let (mut g, rows) = new(n, m);
+  if n == 0 || m == 0 {
+    return (g, rows);
+  }
+  // clockwise from the northwest corner
+  let boundary = topology::boundary(&g, rows[0][0]);
+  let (north, rest) = boundary.split_at(m);
+  let (east, rest) = rest.split_at(n);
+  let (south, west) = rest.split_at(m);
+  glue_sides(&mut g, north, south, wrap_rows);
+  glue_sides(&mut g, east, west, wrap_cols);
+  (g, rows)
+}
+
+/// Grid of the squares where mask[r][c] is true, in the same coordinates as `new`,
+/// with squares sewn together wherever they are adjacent, so that the mask may have holes.
+/// Returns the table of squares as for `new`, with None for absent squares.
+pub fn from_mask(mask: &[Vec<bool>]) -> (GMap, Vec<Vec<Option<Dart>>>) {
+  let cols = mask.iter().map(|row| row.len()).max().unwrap_or(0);
+  masked(&SquareGrid::new(mask.len(), cols), mask)
+}
+
+/// Grid of the squares at the given (row, column) coordinates, as for `from_mask`.
+pub fn from_cells(cells: &[(usize, usize)]) -> (GMap, Vec<Vec<Option<Dart>>>) {
+  from_mask(&mask_of(cells))
+}
+
 pub fn vertex_grid(g: &GMap, squares: &[Vec<Dart>]) -> Vec<Vec<Dart>> {
   squares
     .iter()
@@ -42,3 +77,353 @@ pub fn vertex_grid(g: &GMap, squares: &[Vec<Dart>]) -> Vec<Vec<Dart>> {
     }))
     .collect()
 }
+
+/// Coordinates of the cells of a grid from `new` or `wrapped`, given its table of squares,
+/// as located by `SquareGrid`: vertices in 0..=n, 0..=m and edges in doubled coordinates.
+pub fn coords(g: &GMap, squares: &[Vec<Dart>]) -> GridCoords {
+  let grid = SquareGrid::new(squares.len(), squares.first().map_or(0, |row| row.len()));
+  let signed = |(r, c): (usize, usize)| (r as isize, c as isize);
+  GridCoords::new(g, squares, 4, |d| {
+    [grid.v_loc(d), grid.e_loc2(d), grid.f_loc(d)].map(signed)
+  })
+}
+
+/// Implicit n * m grid; n rows, m columns.
+/// Rows increase from north to south, columns increase from west to east.
+///
+/// Darts are numbered within each square starting from the northwest end of the north edge,
+/// going clockwise: 0 and 1 on the north edge, 2 and 3 on the east edge, and so on.
+/// Dart 0 of each square corresponds to the darts returned by `new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquareGrid {
+  pub rows: usize,
+  pub cols: usize,
+}
+
+/// (row, col) offset of the neighbor across each side
+const NEIGHBORS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+/// (row, col) offset of the vertex of each dart
+const VERTEX_OFFSETS: [(usize, usize); 8] = [
+  (0, 0),
+  (0, 1),
+  (0, 1),
+  (1, 1),
+  (1, 1),
+  (1, 0),
+  (1, 0),
+  (0, 0),
+];
+/// doubled (row, col) offset of the edge of each dart
+const EDGE_OFFSETS: [(usize, usize); 4] = [(0, 1), (1, 2), (2, 1), (1, 0)];
+
+impl SquareGrid {
+  pub fn new(rows: usize, cols: usize) -> Self {
+    Self { rows, cols }
+  }
+
+  pub fn has_square(&self, r: isize, c: isize) -> bool {
+    0 <= r && r < self.rows as isize && 0 <= c && c < self.cols as isize
+  }
+
+  /// Dart 0 of the square at (r, c)
+  pub fn face(&self, r: usize, c: usize) -> Option<GridDart> {
+    if self.has_square(r as isize, c as isize) {
+      Some(GridDart {
+        row: r,
+        col: c,
+        i: 0,
+      })
+    } else {
+      None
+    }
+  }
+
+  /// location of face
+  pub fn f_loc(&self, d: GridDart) -> (usize, usize) {
+    (d.row, d.col)
+  }
+
+  /// location of vertex, in 0..=rows, 0..=cols
+  pub fn v_loc(&self, d: GridDart) -> (usize, usize) {
+    let (ro, co) = VERTEX_OFFSETS[d.i];
+    (d.row + ro, d.col + co)
+  }
+
+  /// location of edge in doubled coordinates
+  pub fn e_loc2(&self, d: GridDart) -> (usize, usize) {
+    let (ro, co) = EDGE_OFFSETS[d.i / 2];
+    (2 * d.row + ro, 2 * d.col + co)
+  }
+
+  fn rep(&self, r: usize, c: usize, i: usize, a: Alphas) -> GridDart {
+    assert!(self.has_square(r as isize, c as isize));
+    self.orbit(GridDart { row: r, col: c, i }, a).min().unwrap()
+  }
+
+  pub fn e_top(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 0, Alphas::EDGE)
+  }
+  pub fn e_right(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 2, Alphas::EDGE)
+  }
+  pub fn e_bottom(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 4, Alphas::EDGE)
+  }
+  pub fn e_left(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 6, Alphas::EDGE)
+  }
+  pub fn v_tl(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 0, Alphas::VERTEX)
+  }
+  pub fn v_tr(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 2, Alphas::VERTEX)
+  }
+  pub fn v_br(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 4, Alphas::VERTEX)
+  }
+  pub fn v_bl(&self, r: usize, c: usize) -> GridDart {
+    self.rep(r, c, 6, Alphas::VERTEX)
+  }
+
+  /// Vertex at the given location, in 0..=rows, 0..=cols
+  pub fn v_at_loc(&self, y: usize, x: usize) -> Option<GridDart> {
+    if y > self.rows || x > self.cols || self.rows == 0 || self.cols == 0 {
+      return None;
+    }
+    Some(match (y == self.rows, x == self.cols) {
+      (true, true) => self.v_br(y - 1, x - 1),
+      (true, false) => self.v_bl(y - 1, x),
+      (false, true) => self.v_tr(y, x - 1),
+      (false, false) => self.v_tl(y, x),
+    })
+  }
+
+  /// Convert to the explicit representation,
+  /// also returning the table of squares in the same format as `new`.
+  pub fn to_explicit(&self) -> (GMap, Vec<Vec<Dart>>) {
+    let (g, m) = self.to_gmap();
+    let rows = (0..self.rows)
+      .map(|r| {
+        (0..self.cols)
+          .map(|c| {
+            m[&GridDart {
+              row: r,
+              col: c,
+              i: 0,
+            }]
+          })
+          .collect()
+      })
+      .collect();
+    (g, rows)
+  }
+}
+
+impl GMapLike for SquareGrid {
+  type Dart = GridDart;
+
+  fn dimension(&self) -> usize {
+    2
+  }
+
+  fn darts(&self) -> impl Iterator<Item = GridDart> + '_ {
+    (0..self.rows)
+      .cartesian_product(0..self.cols)
+      .cartesian_product(0..8)
+      .map(|((row, col), i)| GridDart { row, col, i })
+  }
+
+  fn alpha(&self, d: GridDart, j: usize) -> GridDart {
+    match j {
+      0 | 1 => GridDart {
+        i: polygon_alpha(4, d.i, j),
+        ..d
+      },
+      2 => {
+        let (ro, co) = NEIGHBORS[d.i / 2];
+        let r = d.row as isize + ro;
+        let c = d.col as isize + co;
+        if self.has_square(r, c) {
+          GridDart {
+            row: r as usize,
+            col: c as usize,
+            i: opposite_side(4, d.i),
+          }
+        } else {
+          d
+        }
+      }
+      _ => d,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::collections::HashMap;
+
+  #[test]
+  fn test_implicit_matches_explicit() {
+    let (g, squares) = new(3, 4);
+    let grid = SquareGrid::new(3, 4);
+    // walk both maps in parallel from the corresponding squares
+    let mut m = HashMap::new();
+    let mut stack = vec![(
+      GridDart {
+        row: 0,
+        col: 0,
+        i: 0,
+      },
+      squares[0][0],
+    )];
+    while let Some((d, e)) = stack.pop() {
+      if let Some(&e1) = m.get(&d) {
+        assert_eq!(e1, e);
+        continue;
+      }
+      m.insert(d, e);
+      for j in 0..=2 {
+        stack.push((grid.alpha(d, j), g[(e, j)]));
+      }
+    }
+    assert_eq!(m.len(), g.darts().count());
+    for r in 0..3 {
+      for c in 0..4 {
+        assert_eq!(m[&grid.face(r, c).unwrap()], squares[r][c]);
+      }
+    }
+
+    let (g, rows) = grid.to_explicit();
+    assert_eq!(g.one_dart_per_cell(0).count(), 20);
+    assert_eq!(g.one_dart_per_cell(1).count(), 31);
+    assert_eq!(g.one_dart_per_cell(2).count(), 12);
+    assert_eq!(g.al(rows[1][2], [0, 1, 2, 1]), rows[1][3]);
+  }
+
+  #[test]
+  fn test_locations() {
+    let grid = SquareGrid::new(2, 2);
+    for d in grid.darts() {
+      assert_eq!(grid.v_loc(d), grid.v_loc(grid.alpha(d, 1)));
+      assert_eq!(grid.v_loc(d), grid.v_loc(grid.alpha(d, 2)));
+      assert_eq!(grid.e_loc2(d), grid.e_loc2(grid.alpha(d, 0)));
+      assert_eq!(grid.e_loc2(d), grid.e_loc2(grid.alpha(d, 2)));
+    }
+    assert_eq!(grid.e_right(0, 0), grid.e_left(0, 1));
+    assert_eq!(grid.v_br(0, 0), grid.v_tl(1, 1));
+    assert_eq!(grid.v_loc(grid.v_at_loc(2, 1).unwrap()), (2, 1));
+    assert_eq!(grid.v_loc(grid.v_at_loc(2, 2).unwrap()), (2, 2));
+    assert_eq!(grid.v_at_loc(3, 0), None);
+  }
+
+  #[test]
+  fn test_from_mask() {
+    use crate::isomorphism::is_isomorphic;
+    use crate::topology::{self, Surface};
+
+    let (g, squares) = from_mask(&vec![vec![true; 3]; 2]);
+    assert!(is_isomorphic(&g, &new(2, 3).0));
+    assert!(squares.iter().flatten().all(|s| s.is_some()));
+
+    // a cross, with ragged rows
+    let (g, squares) = from_mask(&[vec![false, true], vec![true, true, true], vec![false, true]]);
+    assert_eq!(squares[0], vec![None, squares[0][1]]);
+    assert_eq!(squares[2].len(), 2);
+    let c = topology::components(&g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (12, 16, 5));
+    assert_eq!(c[0].surface(), Some(Surface::DISK));
+    let center = squares[1][1].unwrap();
+    assert!(g.cycle(center, &[0, 1]).all(|d| !g.is_free(d, 2)));
+    assert_eq!(g[(center, 2)], g.al(squares[0][1].unwrap(), [1, 0, 1]));
+
+    // a ring of squares around a hole
+    let cells: Vec<(usize, usize)> = (0..3)
+      .flat_map(|r| (0..3).map(move |c| (r, c)))
+      .filter(|&p| p != (1, 1))
+      .collect();
+    let (g, squares) = from_cells(&cells);
+    assert_eq!(squares[1][1], None);
+    let c = topology::components(&g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::CYLINDER));
+    assert_eq!(c[0].faces, 8);
+
+    // squares meeting only at a corner are not joined
+    let (g, _) = from_cells(&[(0, 0), (1, 1)]);
+    assert_eq!(topology::components(&g).unwrap().len(), 2);
+  }
+
+  #[test]
+  fn test_coords() {
+    let (g, squares) = new(2, 3);
+    let coords = coords(&g, &squares);
+    for d in g.darts() {
+      for a in [Alphas::VERTEX, Alphas::EDGE, Alphas::FACE] {
+        let p = coords.coords_of(d, a).unwrap();
+        assert!(g.orbit(d, a).contains(&coords.dart_at(a, p).unwrap()));
+      }
+    }
+    assert_eq!(coords.face_at(1, 2), Some(squares[1][2]));
+    assert_eq!(coords.face_at(2, 0), None);
+    assert_eq!(
+      coords.coords_of(squares[1][2], Alphas::VERTEX),
+      Some((1, 2))
+    );
+    assert_eq!(coords.coords_of(squares[1][2], Alphas::EDGE), Some((2, 5)));
+    assert_eq!(coords.edge_between((0, 0), (0, 1)), Some(squares[0][0]));
+    assert_eq!(
+      coords.edge_between((2, 3), (1, 3)),
+      Some(g.al(squares[1][2], [0, 1, 0]))
+    );
+    assert_eq!(coords.edge_between((0, 0), (1, 1)), None);
+    assert_eq!(coords.coords_of(squares[0][0], Alphas::HALF_EDGE), None);
+
+    // on a torus, the vertices along the seams have two or four coordinates
+    let (g, squares) = wrapped(2, 3, Wrap::Straight, Wrap::Straight);
+    let coords = super::coords(&g, &squares);
+    let corner = g
+      .cell(coords.vertex_at((0, 0)).unwrap(), 0)
+      .collect::<Vec<_>>();
+    for p in [(0, 3), (2, 0), (2, 3)] {
+      assert!(corner.contains(&coords.vertex_at(p).unwrap()));
+    }
+    assert!(g
+      .cell(coords.edge_at2((0, 1)).unwrap(), 1)
+      .any(|d| d == coords.edge_at2((4, 1)).unwrap()));
+  }
+
+  #[test]
+  fn test_wrapped() {
+    use crate::topology::{self, Surface};
+    use Wrap::*;
+    for (wrap_rows, wrap_cols, surface) in [
+      (None, None, Surface::DISK),
+      (Straight, None, Surface::CYLINDER),
+      (None, Twisted, Surface::MOBIUS_STRIP),
+      (Straight, Straight, Surface::TORUS),
+      (Twisted, Straight, Surface::KLEIN_BOTTLE),
+      (Straight, Twisted, Surface::KLEIN_BOTTLE),
+      (Twisted, Twisted, Surface::PROJECTIVE_PLANE),
+    ] {
+      for (n, m) in [(1, 1), (2, 3), (4, 3)] {
+        let (g, rows) = wrapped(n, m, wrap_rows, wrap_cols);
+        assert_eq!(rows, new(n, m).1);
+        let c = topology::components(&g).unwrap();
+        assert_eq!(c.len(), 1);
+        assert_eq!(
+          c[0].surface(),
+          Some(surface),
+          "{:?} {:?} {}x{}",
+          wrap_rows,
+          wrap_cols,
+          n,
+          m
+        );
+        if c[0].boundaries == 0 && surface != Surface::PROJECTIVE_PLANE {
+          assert!(g.one_dart_per_cell(0).all(|v| g.cell(v, 0).count() == 8));
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,216 @@
+use crate::{Alphas, Dart, GMap, GMapLike, OrbitMap};
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+/// Grid of triangles in n rows, each consisting of m rhombi split into
+/// a down-pointing and an up-pointing triangle, so that each row has 2m triangles
+/// starting with a down-pointing one.
+/// Like `hex::new`, the grid is a rhombus whose rows are offset to the east going south.
+///
+/// Each triangle is the dart at the triangle's west vertex, on the side going clockwise:
+/// the north side of a down-pointing triangle, or the west side of an up-pointing one.
+pub fn new(n: usize, m: usize) -> (GMap, Vec<Vec<Dart>>) {
+  patch(&vec![(0, 2 * m); n])
+}
+
+/// Rectangular patch of triangles in n rows of 2m triangles, each starting with a down-pointing one
+/// as for `new`, but with every second row shifted back by a rhombus,
+/// so that the west and east sides zigzag around vertical lines instead of slanting.
+pub fn rectangle(n: usize, m: usize) -> (GMap, Vec<Vec<Dart>>) {
+  let rows: Vec<(isize, usize)> = (0..n as isize).map(|r| (-2 * (r / 2), 2 * m)).collect();
+  patch(&rows)
+}
+
+/// Hexagonal patch of triangles with k triangles along each side, in 2k rows.
+/// Rows are numbered as for `new`, and each row starts
+/// with an up-pointing triangle in the northern half and a down-pointing one in the southern half.
+pub fn hexagon(k: usize) -> (GMap, Vec<Vec<Dart>>) {
+  let k = k as isize;
+  let rows: Vec<(isize, usize)> = (0..2 * k)
+    .map(|r| {
+      if r < k {
+        (2 * (k - r) - 1, (2 * (k + r) + 1) as usize)
+      } else {
+        (0, (6 * k - 2 * r - 1) as usize)
+      }
+    })
+    .collect();
+  patch(&rows)
+}
+
+/// Build rows of triangles, given the range of positions within each row.
+/// Position 2x of row r is the down-pointing triangle with vertices (x, r), (x + 1, r), (x, r + 1),
+/// and position 2x + 1 the up-pointing triangle with vertices (x + 1, r), (x, r + 1), (x + 1, r + 1),
+/// in the coordinates of `vertex_coords`.
+fn patch(rows: &[(isize, usize)]) -> (GMap, Vec<Vec<Dart>>) {
+  let mut g = GMap::empty(2).unwrap();
+  let triangles: Vec<Vec<Dart>> = rows
+    .iter()
+    .map(|&(_, len)| (0..len).map(|_| g.add_polygon(3)).collect())
+    .collect();
+  let at: HashMap<(usize, isize), Dart> = rows
+    .iter()
+    .zip(&triangles)
+    .enumerate()
+    .flat_map(|(r, (&(t0, _), row))| {
+      row
+        .iter()
+        .enumerate()
+        .map(move |(j, &d)| ((r, t0 + j as isize), d))
+    })
+    .collect();
+
+  for (r, &(t0, len)) in rows.iter().enumerate() {
+    for (t, (&d0, &d1)) in (t0..).zip(triangles[r].iter().tuple_windows()) {
+      if t % 2 == 0 {
+        g.sew(2, g.al(d0, [0, 1]), g.al(d1, [0])).unwrap();
+      } else {
+        g.sew(2, g.al(d0, [0, 1]), g.al(d1, [1])).unwrap();
+      }
+    }
+    // up-pointing triangles share their south side with down-pointing triangles below
+    for t in t0..t0 + len as isize {
+      if t % 2 != 0 {
+        if let Some(&below) = at.get(&(r + 1, t - 1)) {
+          g.sew(2, g.al(at[&(r, t)], [1]), below).unwrap();
+        }
+      }
+    }
+  }
+
+  (g, triangles)
+}
+
+/// Offsets of the six directions along edges, clockwise from east:
+/// the coordinates are along basis vectors pointing east and southeast,
+/// each the length of an edge.
+const DIRECTIONS: [(isize, isize); 6] = [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)];
+
+/// Integer coordinates of each vertex along basis vectors pointing east and southeast,
+/// each the length of an edge, so that the second coordinate is the number of rows to the north.
+/// The first triangle of the last row must point down as in `new` and `hexagon`,
+/// and its west vertex is placed at (0, n - 1),
+/// which puts the northwest corner of `new` at the origin.
+pub fn vertex_coords(g: &GMap, rows: &[Vec<Dart>]) -> OrbitMap<(isize, isize)> {
+  let mut coords = OrbitMap::new(Alphas::VERTEX);
+  let start = match rows.last().and_then(|row| row.first()) {
+    Some(&d) => d,
+    None => return coords,
+  };
+  // the position of each dart's vertex, the direction of its edge,
+  // and whether its face is clockwise from the edge
+  let mut states = HashMap::new();
+  states.insert(start, ((0, rows.len() as isize - 1), 0, true));
+  let mut stack = vec![start];
+  while let Some(d) = stack.pop() {
+    let ((x, y), dir, cw) = states[&d];
+    let (dx, dy) = DIRECTIONS[dir];
+    let turn = if cw { 1 } else { 5 };
+    for (e, state) in [
+      (g[(d, 0)], ((x + dx, y + dy), (dir + 3) % 6, !cw)),
+      (g[(d, 1)], ((x, y), (dir + turn) % 6, !cw)),
+      (g[(d, 2)], ((x, y), dir, !cw)),
+    ] {
+      states.entry(e).or_insert_with(|| {
+        stack.push(e);
+        state
+      });
+    }
+  }
+  for v in g.one_dart_per_incident_orbit(start, Alphas::VERTEX, Alphas(!0)) {
+    coords.insert(g, v, states[&v].0);
+  }
+  coords
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::topology::{self, Surface};
+
+  use std::collections::HashSet;
+
+  /// Check that coordinates are consistent with the map and distinct.
+  fn check_coords(g: &GMap, coords: &OrbitMap<(isize, isize)>) {
+    for d in g.darts() {
+      let (x0, y0) = coords.map()[&d];
+      let (x1, y1) = coords.map()[&g[(d, 0)]];
+      assert!(DIRECTIONS.contains(&(x1 - x0, y1 - y0)));
+    }
+    let distinct: HashSet<(isize, isize)> = coords.map().values().cloned().collect();
+    assert_eq!(distinct.len(), g.one_dart_per_cell(0).count());
+  }
+
+  #[test]
+  fn test_new() {
+    let (g, rows) = new(2, 3);
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row.len() == 6));
+    let c = topology::components(&g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (12, 23, 12));
+    assert_eq!(c[0].surface(), Some(Surface::DISK));
+    assert!(g.is_oriented());
+
+    let coords = vertex_coords(&g, &rows);
+    check_coords(&g, &coords);
+    assert_eq!(coords.map()[&rows[0][0]], (0, 0));
+    assert_eq!(coords.map()[&g.al(rows[0][0], [0])], (1, 0));
+    assert_eq!(coords.map()[&g.al(rows[0][1], [0])], (1, 0));
+    assert_eq!(coords.map()[&g.al(rows[1][5], [0, 1, 0])], (3, 2));
+  }
+
+  #[test]
+  fn test_rectangle() {
+    let (n, m) = (4, 3);
+    let (g, rows) = rectangle(n, m);
+    assert!(rows.iter().all(|row| row.len() == 2 * m));
+    let c = topology::components(&g).unwrap();
+    // rows 1 and 2 are offset by half an edge, so the line between them has an extra vertex
+    assert_eq!(
+      (c[0].vertices, c[0].faces),
+      ((n + 1) * (m + 1) + 1, 2 * n * m)
+    );
+    assert_eq!(c[0].surface(), Some(Surface::DISK));
+    assert!(g.is_oriented());
+
+    let coords = vertex_coords(&g, &rows);
+    check_coords(&g, &coords);
+    let xs = coords.map().values().map(|&(x, _)| x);
+    let ys = coords.map().values().map(|&(_, y)| y);
+    assert_eq!(xs.minmax().into_option(), Some((0, 4)));
+    assert_eq!(ys.minmax().into_option(), Some((0, 4)));
+    // twice the distance east of each vertex stays between the sides
+    let easts = coords.map().values().map(|&(x, y)| 2 * x + y);
+    assert_eq!(easts.minmax().into_option(), Some((2, 10)));
+  }
+
+  #[test]
+  fn test_hexagon() {
+    let k = 3;
+    let (g, rows) = hexagon(k);
+    assert_eq!(rows.iter().map(|row| row.len()).sum::<usize>(), 6 * k * k);
+    let c = topology::components(&g).unwrap();
+    assert_eq!(c[0].vertices, 3 * k * k + 3 * k + 1);
+    assert_eq!(c[0].surface(), Some(Surface::DISK));
+    assert_eq!(topology::boundaries(&g).unwrap()[0].len(), 6 * k);
+    assert!(g.is_oriented());
+
+    let coords = vertex_coords(&g, &rows);
+    check_coords(&g, &coords);
+    let k = k as isize;
+    for &(x, y) in coords.map().values() {
+      assert!((x - k).abs() <= k && (y - k).abs() <= k && (x + y - 2 * k).abs() <= k);
+    }
+    // interior vertices have degree 6
+    let interior: Vec<Dart> = g
+      .one_dart_per_cell(0)
+      .filter(|&v| g.cell(v, 0).all(|d| !g.is_free(d, 2)))
+      .collect();
+    assert!(interior.iter().all(|&v| g.cell(v, 0).count() == 12));
+    assert_eq!(interior.len(), 3 * (k * k - k) as usize + 1);
+
+    assert!(hexagon(0).1.is_empty());
+  }
+}
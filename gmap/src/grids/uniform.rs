@@ -0,0 +1,528 @@
+use super::from_polygons;
+use super::periodic::TranslationUnit;
+use crate::{Alphas, Dart, GMap, GMapLike, OrbitMap};
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// The 11 edge-to-edge tilings of the plane by regular polygons with all vertices alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Archimedean {
+  Square,
+  Triangular,
+  Hexagonal,
+  Trihexagonal,
+  SnubSquare,
+  SnubTrihexagonal,
+  ElongatedTriangular,
+  TruncatedSquare,
+  TruncatedHexagonal,
+  Rhombitrihexagonal,
+  TruncatedTrihexagonal,
+}
+
+/// The 11 duals of the Archimedean tilings, whose faces are all alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Laves {
+  Square,
+  Triangular,
+  Hexagonal,
+  Rhombille,
+  CairoPentagonal,
+  Floret,
+  PrismaticPentagonal,
+  TetrakisSquare,
+  TriakisTriangular,
+  DeltoidalTrihexagonal,
+  Kisrhombille,
+}
+
+impl Archimedean {
+  pub const ALL: [Archimedean; 11] = [
+    Archimedean::Square,
+    Archimedean::Triangular,
+    Archimedean::Hexagonal,
+    Archimedean::Trihexagonal,
+    Archimedean::SnubSquare,
+    Archimedean::SnubTrihexagonal,
+    Archimedean::ElongatedTriangular,
+    Archimedean::TruncatedSquare,
+    Archimedean::TruncatedHexagonal,
+    Archimedean::Rhombitrihexagonal,
+    Archimedean::TruncatedTrihexagonal,
+  ];
+
+  /// Number of sides of the faces around each vertex, in cyclic order.
+  pub fn vertex_configuration(self) -> &'static [usize] {
+    match self {
+      Archimedean::Square => &[4, 4, 4, 4],
+      Archimedean::Triangular => &[3, 3, 3, 3, 3, 3],
+      Archimedean::Hexagonal => &[6, 6, 6],
+      Archimedean::Trihexagonal => &[3, 6, 3, 6],
+      Archimedean::SnubSquare => &[3, 3, 4, 3, 4],
+      Archimedean::SnubTrihexagonal => &[3, 3, 3, 3, 6],
+      Archimedean::ElongatedTriangular => &[3, 3, 3, 4, 4],
+      Archimedean::TruncatedSquare => &[4, 8, 8],
+      Archimedean::TruncatedHexagonal => &[3, 12, 12],
+      Archimedean::Rhombitrihexagonal => &[3, 4, 6, 4],
+      Archimedean::TruncatedTrihexagonal => &[4, 6, 12],
+    }
+  }
+
+  pub fn dual(self) -> Laves {
+    match self {
+      Archimedean::Square => Laves::Square,
+      Archimedean::Triangular => Laves::Hexagonal,
+      Archimedean::Hexagonal => Laves::Triangular,
+      Archimedean::Trihexagonal => Laves::Rhombille,
+      Archimedean::SnubSquare => Laves::CairoPentagonal,
+      Archimedean::SnubTrihexagonal => Laves::Floret,
+      Archimedean::ElongatedTriangular => Laves::PrismaticPentagonal,
+      Archimedean::TruncatedSquare => Laves::TetrakisSquare,
+      Archimedean::TruncatedHexagonal => Laves::TriakisTriangular,
+      Archimedean::Rhombitrihexagonal => Laves::DeltoidalTrihexagonal,
+      Archimedean::TruncatedTrihexagonal => Laves::Kisrhombille,
+    }
+  }
+
+  /// Translation unit of the tiling, from which `periodic::patch` builds patches
+  /// like those of `archimedean`, optionally wrapped around.
+  pub fn unit(self) -> TranslationUnit {
+    let (u, v, _) = self.prototiles();
+    let (g, _, layout) = archimedean(self, 1, 1);
+    TranslationUnit::from_layout(g, layout, [v.to_f64(), u.to_f64()])
+  }
+
+  /// Translation vectors of the tiling, and one face from each class of faces under translation,
+  /// given by a vertex, the direction of the next edge counterclockwise, and the number of sides.
+  fn prototiles(self) -> (Point, Point, Vec<(Point, usize, usize)>) {
+    let p = Point::sum;
+    match self {
+      Archimedean::Square => (p(&[0]), p(&[6]), vec![(p(&[]), 0, 4)]),
+      Archimedean::Triangular => (p(&[0]), p(&[4]), vec![(p(&[]), 0, 3), (p(&[0]), 4, 3)]),
+      Archimedean::Hexagonal => (p(&[0, 4]), p(&[4, 8]), vec![(p(&[]), 0, 6)]),
+      Archimedean::Trihexagonal => (
+        p(&[0, 0]),
+        p(&[4, 4]),
+        vec![(p(&[]), 0, 6), (p(&[0]), 0, 3), (p(&[4, 4]), 20, 3)],
+      ),
+      Archimedean::SnubSquare => (
+        p(&[0, 2]),
+        p(&[6, 8]),
+        vec![
+          (p(&[]), 18, 4),
+          (p(&[]), 8, 4),
+          (p(&[]), 0, 3),
+          (p(&[]), 4, 3),
+          (p(&[]), 14, 3),
+          (p(&[0]), 18, 3),
+        ],
+      ),
+      // a triangular tiling with a sublattice of vertices removed, leaving hexagons
+      Archimedean::SnubTrihexagonal => (
+        p(&[0, 0, 4]),
+        p(&[4, 4, 8]),
+        vec![
+          (p(&[0]), 8, 6),
+          (p(&[0]), 0, 3),
+          (p(&[4]), 0, 3),
+          (p(&[8]), 0, 3),
+          (p(&[20]), 0, 3),
+          (p(&[0]), 4, 3),
+          (p(&[4]), 4, 3),
+          (p(&[8]), 4, 3),
+          (p(&[12]), 4, 3),
+        ],
+      ),
+      Archimedean::ElongatedTriangular => (
+        p(&[0]),
+        p(&[4, 6]),
+        vec![(p(&[]), 0, 4), (p(&[6]), 0, 3), (p(&[6]), 4, 3)],
+      ),
+      // 1 + sqrt(2) = e(0) + e(3) + e(21)
+      Archimedean::TruncatedSquare => (
+        p(&[0, 3, 21]),
+        p(&[6, 9, 3]),
+        vec![(p(&[]), 0, 8), (p(&[0, 3]), 15, 4)],
+      ),
+      // 2 + sqrt(3) = 2 e(0) + e(2) + e(22)
+      Archimedean::TruncatedHexagonal => (
+        p(&[2, 2, 4, 0]),
+        p(&[6, 6, 8, 4]),
+        vec![
+          (p(&[]), 0, 12),
+          (p(&[0, 2]), 14, 3),
+          (p(&[0, 2, 4, 6]), 18, 3),
+        ],
+      ),
+      Archimedean::Rhombitrihexagonal => (
+        p(&[2, 4, 0]),
+        p(&[6, 8, 4]),
+        vec![
+          (p(&[]), 0, 6),
+          (p(&[0]), 12, 4),
+          (p(&[0, 4]), 16, 4),
+          (p(&[0, 4, 8]), 20, 4),
+          (p(&[0]), 18, 3),
+          (p(&[0, 4]), 22, 3),
+        ],
+      ),
+      Archimedean::TruncatedTrihexagonal => (
+        p(&[2, 2, 2, 4, 0]),
+        p(&[6, 6, 6, 8, 4]),
+        vec![
+          (p(&[]), 0, 12),
+          (p(&[0]), 12, 4),
+          (p(&[0, 2, 4]), 16, 4),
+          (p(&[0, 2, 4, 6, 8]), 20, 4),
+          (p(&[0, 2]), 14, 6),
+          (p(&[0, 2, 4, 6]), 18, 6),
+        ],
+      ),
+    }
+  }
+}
+
+impl Laves {
+  pub const ALL: [Laves; 11] = [
+    Laves::Square,
+    Laves::Triangular,
+    Laves::Hexagonal,
+    Laves::Rhombille,
+    Laves::CairoPentagonal,
+    Laves::Floret,
+    Laves::PrismaticPentagonal,
+    Laves::TetrakisSquare,
+    Laves::TriakisTriangular,
+    Laves::DeltoidalTrihexagonal,
+    Laves::Kisrhombille,
+  ];
+
+  /// Translation unit of the tiling, from which `periodic::patch` builds patches
+  /// like those of `laves`, optionally wrapped around.
+  pub fn unit(self) -> TranslationUnit {
+    let (u, v, _) = self.dual().prototiles();
+    let (g, _, layout) = laves(self, 1, 1);
+    TranslationUnit::from_layout(g, layout, [v.to_f64(), u.to_f64()])
+  }
+
+  pub fn dual(self) -> Archimedean {
+    *Archimedean::ALL.iter().find(|t| t.dual() == self).unwrap()
+  }
+}
+
+/// Point of the plane as an integer combination of the unit vectors e(k) at angles of k * 15 degrees,
+/// stored as coefficients of e(0), ..., e(7), so that equal points compare equal.
+/// The rest reduce using e(k + 12) = -e(k) and e(k + 8) = e(k + 4) - e(k).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Point([i64; 8]);
+
+impl Point {
+  fn e(k: usize) -> Self {
+    let k = k % 24;
+    if k >= 12 {
+      -Point::e(k - 12)
+    } else if k >= 8 {
+      Point::e(k - 4) - Point::e(k - 8)
+    } else {
+      let mut c = [0; 8];
+      c[k] = 1;
+      Point(c)
+    }
+  }
+
+  fn sum(ks: &[usize]) -> Self {
+    ks.iter().fold(Point([0; 8]), |p, &k| p + Point::e(k))
+  }
+
+  fn to_f64(self) -> (f64, f64) {
+    self.0.iter().enumerate().fold((0., 0.), |(x, y), (k, &c)| {
+      let a = k as f64 * PI / 12.;
+      (x + c as f64 * a.cos(), y + c as f64 * a.sin())
+    })
+  }
+}
+
+impl Add for Point {
+  type Output = Point;
+  fn add(self, other: Point) -> Point {
+    Point(std::array::from_fn(|k| self.0[k] + other.0[k]))
+  }
+}
+
+impl Sub for Point {
+  type Output = Point;
+  fn sub(self, other: Point) -> Point {
+    self + -other
+  }
+}
+
+impl Neg for Point {
+  type Output = Point;
+  fn neg(self) -> Point {
+    Point(self.0.map(|c| -c))
+  }
+}
+
+impl Mul<i64> for Point {
+  type Output = Point;
+  fn mul(self, x: i64) -> Point {
+    Point(self.0.map(|c| c * x))
+  }
+}
+
+/// Vertices of the regular polygon with unit sides starting at p with an edge in direction k,
+/// going counterclockwise.
+fn polygon(p: Point, k: usize, sides: usize) -> Vec<Point> {
+  (0..sides)
+    .scan(p, |q, j| {
+      let v = *q;
+      *q = *q + Point::e(k + j * 24 / sides);
+      Some(v)
+    })
+    .collect()
+}
+
+/// A patch of a tiling: the map, the faces of each translation unit by row and column,
+/// and the position of each vertex.
+pub type Tiling = (GMap, Vec<Vec<Vec<Dart>>>, OrbitMap<(f64, f64)>);
+
+/// Patch of n rows of m translation units of an Archimedean tiling.
+/// Rows go in the direction of increasing y, so that they go down the screen in SVG coordinates.
+///
+/// Returns the map, the faces in each translation unit (as the dart at their first vertex),
+/// and the position of each vertex with unit edge lengths.
+/// Positions are computed exactly before conversion, so they can be used directly as a layout.
+pub fn archimedean(t: Archimedean, n: usize, m: usize) -> Tiling {
+  let (g, cells, positions) = archimedean_exact(t, 0..n as i64, 0..m as i64);
+  let layout = layout(&g, &positions, 1);
+  (g, cells, layout)
+}
+
+fn archimedean_exact(
+  t: Archimedean,
+  rows: std::ops::Range<i64>,
+  cols: std::ops::Range<i64>,
+) -> (GMap, Vec<Vec<Vec<Dart>>>, HashMap<Dart, Point>) {
+  let (u, v, tiles) = t.prototiles();
+  let faces: Vec<Vec<Point>> = rows
+    .clone()
+    .flat_map(|r| cols.clone().map(move |c| (r, c)))
+    .flat_map(|(r, c)| {
+      tiles
+        .iter()
+        .map(move |&(p, k, sides)| polygon(p + u * c + v * r, k, sides))
+    })
+    .collect();
+  let (g, darts, positions) = from_polygons(&faces);
+  let cells = darts
+    .chunks(tiles.len().max(1))
+    .map(<[Dart]>::to_vec)
+    .collect::<Vec<_>>()
+    .chunks(cols.count().max(1))
+    .map(<[Vec<Dart>]>::to_vec)
+    .collect();
+  (g, cells, positions)
+}
+
+/// Patch of n rows of m translation units of a Laves tiling, as for `archimedean`.
+/// The faces of each translation unit correspond to the classes of vertices of the dual tiling,
+/// and the vertices are at the centers of the dual tiling's faces.
+pub fn laves(t: Laves, n: usize, m: usize) -> Tiling {
+  const MARGIN: i64 = 2;
+  // positions are scaled up so that the centers of faces have integer coordinates
+  const SCALE: i64 = 24;
+  let t = t.dual();
+  let (u, v, tiles) = t.prototiles();
+  let (d, _, positions) =
+    archimedean_exact(t, -MARGIN..n as i64 + MARGIN, -MARGIN..m as i64 + MARGIN);
+  let at: HashMap<Point, Dart> = positions.iter().map(|(&x, &p)| (p, x)).collect();
+
+  // one vertex from each class under translation, taken from the faces of a single unit
+  let (uf, vf) = (u.to_f64(), v.to_f64());
+  let det = uf.0 * vf.1 - uf.1 * vf.0;
+  let translate = |q: Point, r: Point| {
+    let (x, y) = (q - r).to_f64();
+    let a = ((x * vf.1 - y * vf.0) / det).round() as i64;
+    let b = ((uf.0 * y - uf.1 * x) / det).round() as i64;
+    q - r == u * a + v * b
+  };
+  let mut reps: Vec<Point> = vec![];
+  for &(p, k, sides) in &tiles {
+    for q in polygon(p, k, sides) {
+      if !reps.iter().any(|&r| translate(q, r)) {
+        reps.push(q);
+      }
+    }
+  }
+
+  let center = |x: Dart| {
+    let corners: Vec<Point> = d.cycle(x, &[0, 1]).map(|y| positions[&y]).collect();
+    corners.iter().fold(Point([0; 8]), |s, &q| s + q) * (SCALE / corners.len() as i64)
+  };
+  let mut faces = vec![];
+  for r in 0..n as i64 {
+    for c in 0..m as i64 {
+      for &p in &reps {
+        let x = at[&(p + u * c + v * r)];
+        let mut corners: Vec<Point> = d.cycle(x, &[1, 2]).map(center).collect();
+        debug_assert_eq!(2 * corners.len(), d.cell(x, 0).count());
+        let f: Vec<(f64, f64)> = corners.iter().map(|q| q.to_f64()).collect();
+        let area: f64 = (0..f.len())
+          .map(|j| {
+            let (a, b) = (f[j], f[(j + 1) % f.len()]);
+            a.0 * b.1 - a.1 * b.0
+          })
+          .sum();
+        if area < 0. {
+          corners.reverse();
+        }
+        faces.push(corners);
+      }
+    }
+  }
+
+  let (g, darts, positions) = from_polygons(&faces);
+  let cells = darts
+    .chunks(reps.len().max(1))
+    .map(<[Dart]>::to_vec)
+    .collect::<Vec<_>>()
+    .chunks(m.max(1))
+    .map(<[Vec<Dart>]>::to_vec)
+    .collect();
+  let layout = layout(&g, &positions, SCALE);
+  (g, cells, layout)
+}
+
+fn layout(g: &GMap, positions: &HashMap<Dart, Point>, scale: i64) -> OrbitMap<(f64, f64)> {
+  let mut layout = OrbitMap::new(Alphas::VERTEX);
+  for v in g.one_dart_per_cell(0) {
+    let (x, y) = positions[&v].to_f64();
+    layout.insert(g, v, (x / scale as f64, y / scale as f64));
+  }
+  layout
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::periodic;
+  use crate::isomorphism::is_isomorphic;
+  use crate::topology::{self, Surface};
+
+  fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+  }
+
+  /// Whether all of v's edges are interior.
+  fn interior(g: &GMap, v: Dart) -> bool {
+    g.cell(v, 0).all(|x| !g.is_free(x, 2))
+  }
+
+  /// Whether a is a rotation or reflection of b.
+  fn same_cycle(a: &[usize], b: &[usize]) -> bool {
+    let mut r = a.to_vec();
+    (0..2).any(|_| {
+      r.reverse();
+      (0..r.len()).any(|_| {
+        r.rotate_left(1);
+        r == b
+      })
+    })
+  }
+
+  #[test]
+  fn test_archimedean() {
+    for t in Archimedean::ALL {
+      let (g, cells, layout) = archimedean(t, 3, 4);
+      let c = topology::components(&g).unwrap();
+      assert_eq!(c.len(), 1, "{:?}", t);
+      assert_eq!(c[0].surface(), Some(Surface::DISK), "{:?}", t);
+      assert!(g.is_oriented());
+      assert_eq!((cells.len(), cells[0].len()), (3, 4));
+
+      for d in g.darts() {
+        let l = distance(layout.map()[&d], layout.map()[&g[(d, 0)]]);
+        assert!((l - 1.).abs() < 1e-9, "{:?}", t);
+      }
+      let config = t.vertex_configuration();
+      let mut count = 0;
+      for v in g.one_dart_per_cell(0).filter(|&v| interior(&g, v)) {
+        let sizes: Vec<usize> = g
+          .cycle(v, &[1, 2])
+          .map(|x| g.cycle(x, &[0, 1]).count())
+          .collect();
+        assert!(same_cycle(&sizes, config), "{:?} {:?}", t, sizes);
+        count += 1;
+      }
+      assert!(count > 0);
+    }
+  }
+
+  #[test]
+  fn test_laves() {
+    for t in Laves::ALL {
+      let (g, cells, layout) = laves(t, 3, 2);
+      let c = topology::components(&g).unwrap();
+      assert_eq!(c.len(), 1, "{:?}", t);
+      assert_eq!(c[0].surface(), Some(Surface::DISK), "{:?}", t);
+      assert!(g.is_oriented());
+      assert_eq!((cells.len(), cells[0].len()), (3, 2));
+
+      // one face for each class of vertices of the dual
+      let config = t.dual().vertex_configuration();
+      let (d, dual_cells, _) = archimedean(t.dual(), 1, 1);
+      let sides: usize = dual_cells[0][0]
+        .iter()
+        .map(|&f| d.cycle(f, &[0, 1]).count())
+        .sum();
+      assert_eq!(cells[0][0].len() * config.len(), sides, "{:?}", t);
+
+      // the vertices of each face have the degrees of the dual's vertex configuration
+      for &f in cells.iter().flatten().flatten() {
+        let corners: Vec<Dart> = g.cycle(f, &[0, 1]).collect();
+        if corners.iter().all(|&v| interior(&g, v)) {
+          let degrees: Vec<usize> = corners.iter().map(|&v| g.cell(v, 0).count() / 2).collect();
+          assert!(same_cycle(&degrees, config), "{:?} {:?}", t, degrees);
+        } else {
+          assert_eq!(corners.len(), config.len());
+        }
+      }
+      let positions: Vec<(f64, f64)> = g.one_dart_per_cell(0).map(|v| layout.map()[&v]).collect();
+      for (j, &a) in positions.iter().enumerate() {
+        assert!(positions[..j].iter().all(|&b| distance(a, b) > 0.1));
+      }
+    }
+  }
+
+  #[test]
+  fn test_units() {
+    for t in Archimedean::ALL {
+      let unit = t.unit();
+      let p = periodic::patch(&unit, 2, 3, false, false).unwrap();
+      assert!(is_isomorphic(&p.g, &archimedean(t, 2, 3).0), "{:?}", t);
+
+      let p = periodic::patch(&unit, 2, 3, true, true).unwrap();
+      let c = topology::components(&p.g).unwrap();
+      assert_eq!(c[0].surface(), Some(Surface::TORUS), "{:?}", t);
+      for v in p.g.one_dart_per_cell(0) {
+        let sizes: Vec<usize> = p
+          .g
+          .cycle(v, &[1, 2])
+          .map(|x| p.g.cycle(x, &[0, 1]).count())
+          .collect();
+        assert!(same_cycle(&sizes, t.vertex_configuration()), "{:?}", t);
+      }
+    }
+    for t in Laves::ALL {
+      let unit = t.unit();
+      let p = periodic::patch(&unit, 3, 2, false, false).unwrap();
+      assert!(is_isomorphic(&p.g, &laves(t, 3, 2).0), "{:?}", t);
+
+      let p = periodic::patch(&unit, 3, 2, true, true).unwrap();
+      let c = topology::components(&p.g).unwrap();
+      assert_eq!(c[0].surface(), Some(Surface::TORUS), "{:?}", t);
+    }
+  }
+}
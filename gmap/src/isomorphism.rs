@@ -0,0 +1,317 @@
+use crate::{Alphas, Dart, GMap, GMapLike};
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use itertools::Itertools;
+
+/// Number the darts of d's connected component in breadth-first order from d,
+/// visiting alpha_0, ..., alpha_n in turn.
+/// Returns the darts in order together with the resulting code:
+/// the label of alpha_i of each dart, listed in order.
+/// Two darts have the same code iff some isomorphism maps one to the other.
+pub(crate) fn bfs_code<G: GMapLike + ?Sized>(g: &G, d: G::Dart) -> (Vec<G::Dart>, Vec<usize>) {
+  let n = g.dimension();
+  let mut label = HashMap::new();
+  let mut order = vec![d];
+  label.insert(d, 0);
+  let mut code = vec![];
+  let mut k = 0;
+  while k < order.len() {
+    let x = order[k];
+    for i in 0..=n {
+      let y = g.alpha(x, i);
+      let l = *label.entry(y).or_insert_with(|| {
+        order.push(y);
+        order.len() - 1
+      });
+      code.push(l);
+    }
+    k += 1;
+  }
+  (order, code)
+}
+
+/// Isomorphism-invariant data about a dart, used to cut down the starting darts to try.
+fn invariant<G: GMapLike + ?Sized>(g: &G, d: G::Dart) -> Vec<usize> {
+  let n = g.dimension();
+  (0..n)
+    .map(|i| g.cycle(d, &[i, i + 1]).count())
+    .chain((0..=n).map(|i| g.is_free(d, i) as usize))
+    .collect()
+}
+
+/// Darts from which d's component may be canonically numbered:
+/// those whose invariant is the least common in the component.
+pub(crate) fn candidate_starts<G: GMapLike + ?Sized>(g: &G, d: G::Dart) -> Vec<G::Dart> {
+  let classes = g
+    .orbit(d, Alphas(!0))
+    .map(|x| (invariant(g, x), x))
+    .into_group_map();
+  classes
+    .into_iter()
+    .min_by(|(i0, c0), (i1, c1)| (c0.len(), i0).cmp(&(c1.len(), i1)))
+    .unwrap()
+    .1
+}
+
+/// Canonical numbering of d's connected component:
+/// its darts in canonical order, and the code of that numbering.
+fn canonical_component<G: GMapLike + ?Sized>(g: &G, d: G::Dart) -> (Vec<G::Dart>, Vec<usize>) {
+  candidate_starts(g, d)
+    .into_iter()
+    .map(|s| bfs_code(g, s))
+    .min_by(|(_, c0), (_, c1)| c0.cmp(c1))
+    .unwrap()
+}
+
+/// Renumber g canonically, so that isomorphic maps produce identical results
+/// (and hence identical serializations).
+/// Also returns the mapping from darts of g to darts of the result.
+pub fn canonical_form<G: GMapLike + ?Sized>(g: &G) -> (GMap, HashMap<G::Dart, Dart>) {
+  let components: Vec<(Vec<G::Dart>, Vec<usize>)> = g
+    .one_dart_per_component()
+    .map(|d| canonical_component(g, d))
+    .sorted_by(|(_, c0), (_, c1)| c0.len().cmp(&c1.len()).then(c0.cmp(c1)))
+    .collect();
+  GMap::induced(g, components.into_iter().flat_map(|(darts, _)| darts))
+}
+
+/// Find an isomorphism from g to h, as a bijection between their darts.
+pub fn isomorphism<G, H>(g: &G, h: &H) -> Option<HashMap<G::Dart, H::Dart>>
+where
+  G: GMapLike + ?Sized,
+  H: GMapLike + ?Sized,
+{
+  if g.dimension() != h.dimension() {
+    return None;
+  }
+  let (cg, mg) = canonical_form(g);
+  let (ch, mh) = canonical_form(h);
+  if cg.alpha_slice() != ch.alpha_slice() {
+    return None;
+  }
+  let from_canonical: HashMap<Dart, H::Dart> = mh.into_iter().map(|(x, y)| (y, x)).collect();
+  Some(
+    mg.into_iter()
+      .map(|(x, y)| (x, from_canonical[&y]))
+      .collect(),
+  )
+}
+
+pub fn is_isomorphic<G, H>(g: &G, h: &H) -> bool
+where
+  G: GMapLike + ?Sized,
+  H: GMapLike + ?Sized,
+{
+  isomorphism(g, h).is_some()
+}
+
+/// A dart permutation preserving all alpha_i.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Automorphism<D: Eq + Hash> {
+  pub map: HashMap<D, D>,
+  /// Whether the orientation of each component is preserved,
+  /// or None if the map is not orientable.
+  /// Components which are swapped are oriented compatibly with their canonical forms.
+  pub orientation_preserving: Option<bool>,
+}
+
+fn automorphism<G: GMapLike + ?Sized>(
+  g: &G,
+  colors: &Option<HashMap<G::Dart, bool>>,
+  moved: impl IntoIterator<Item = (G::Dart, G::Dart)>,
+) -> Automorphism<G::Dart> {
+  let mut map: HashMap<G::Dart, G::Dart> = g.darts().map(|d| (d, d)).collect();
+  map.extend(moved);
+  let orientation_preserving = colors
+    .as_ref()
+    .map(|c| map.iter().all(|(x, y)| c[x] == c[y]));
+  Automorphism {
+    map,
+    orientation_preserving,
+  }
+}
+
+/// Orientation of each component, relative to the first dart of its canonical numbering.
+/// None if some component is not orientable.
+fn canonical_orientation<G: GMapLike + ?Sized>(
+  g: &G,
+  components: &[(Vec<G::Dart>, Vec<usize>)],
+) -> Option<HashMap<G::Dart, bool>> {
+  let mut colors = HashMap::new();
+  for (darts, _) in components {
+    colors.extend(g.orientation(darts[0]).ok()?);
+  }
+  Some(colors)
+}
+
+/// A generating set for the automorphism group of g.
+/// Consists of automorphisms of single components,
+/// and swaps of pairs of isomorphic components.
+pub fn automorphism_generators<G: GMapLike + ?Sized>(g: &G) -> Vec<Automorphism<G::Dart>> {
+  let components: Vec<(Vec<G::Dart>, Vec<usize>)> = g
+    .one_dart_per_component()
+    .map(|d| canonical_component(g, d))
+    .collect();
+  let colors = canonical_orientation(g, &components);
+
+  let mut generators = vec![];
+  for group in components
+    .iter()
+    .into_group_map_by(|(_, code)| code)
+    .into_values()
+    .sorted_by_key(|group| group[0].0[0])
+  {
+    let (order0, code0) = group[0];
+    let d0 = order0[0];
+    // automorphisms of a connected map are determined by the image of d0
+    let mut local: Vec<HashMap<G::Dart, G::Dart>> = vec![];
+    let mut reached = HashSet::from([d0]);
+    for s in candidate_starts(g, d0).into_iter().sorted() {
+      if reached.contains(&s) {
+        continue;
+      }
+      let (order, code) = bfs_code(g, s);
+      if code != *code0 {
+        continue;
+      }
+      local.push(order0.iter().cloned().zip(order).collect());
+      let mut stack = vec![d0];
+      reached = HashSet::from([d0]);
+      while let Some(x) = stack.pop() {
+        for m in &local {
+          if reached.insert(m[&x]) {
+            stack.push(m[&x]);
+          }
+        }
+      }
+    }
+    generators.extend(local.into_iter().map(|m| automorphism(g, &colors, m)));
+
+    for ((o0, _), (o1, _)) in group.iter().tuple_windows() {
+      let swap = o0
+        .iter()
+        .cloned()
+        .zip(o1.iter().cloned())
+        .flat_map(|(x, y)| [(x, y), (y, x)]);
+      generators.push(automorphism(g, &colors, swap));
+    }
+  }
+  generators
+}
+
+/// All automorphisms of g, starting with the identity.
+/// The orientation-preserving subgroup consists of those flagged as such.
+/// Note that the group can be very large if g has many isomorphic components.
+pub fn automorphisms<G: GMapLike + ?Sized>(g: &G) -> Vec<Automorphism<G::Dart>> {
+  let components: Vec<(Vec<G::Dart>, Vec<usize>)> = g
+    .one_dart_per_component()
+    .map(|d| canonical_component(g, d))
+    .collect();
+  let colors = canonical_orientation(g, &components);
+  let generators = automorphism_generators(g);
+  let darts: Vec<G::Dart> = g.darts().collect();
+
+  let mut group = vec![automorphism(g, &colors, [])];
+  let mut seen = HashSet::from([darts.clone()]);
+  let mut k = 0;
+  while k < group.len() {
+    for s in &generators {
+      let image: Vec<G::Dart> = darts.iter().map(|d| s.map[&group[k].map[d]]).collect();
+      if seen.contains(&image) {
+        continue;
+      }
+      let a = automorphism(g, &colors, darts.iter().cloned().zip(image.iter().cloned()));
+      seen.insert(image);
+      group.push(a);
+    }
+    k += 1;
+  }
+  group
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::square::{self, SquareGrid};
+
+  #[test]
+  fn test_isomorphism() {
+    let (g, _) = square::new(2, 3);
+    let grid = SquareGrid::new(2, 3);
+    let m = isomorphism(&g, &grid).unwrap();
+    assert_eq!(m.len(), 48);
+    for d in g.darts() {
+      for i in 0..=2 {
+        assert_eq!(m[&g[(d, i)]], grid.alpha(m[&d], i));
+      }
+    }
+    assert!(is_isomorphic(&g, &SquareGrid::new(3, 2)));
+    assert!(!is_isomorphic(&g, &SquareGrid::new(1, 6)));
+
+    let (c0, _) = canonical_form(&g);
+    let (c1, _) = canonical_form(&SquareGrid::new(3, 2));
+    assert_eq!(c0.alpha_slice(), c1.alpha_slice());
+
+    // components are matched up regardless of order
+    let mut g = GMap::empty(2).unwrap();
+    g.add_polygon(3);
+    g.add_polygon(4);
+    let mut h = GMap::empty(2).unwrap();
+    h.add_polygon(4);
+    h.add_polygon(3);
+    assert!(is_isomorphic(&g, &h));
+    h.add_polygon(3);
+    assert!(!is_isomorphic(&g, &h));
+  }
+
+  #[test]
+  fn test_automorphisms() {
+    let (g, squares) = square::new(3, 3);
+    let group = automorphisms(&g);
+    assert_eq!(group.len(), 8);
+    assert_eq!(
+      group
+        .iter()
+        .filter(|a| a.orientation_preserving == Some(true))
+        .count(),
+      4
+    );
+    for a in &group {
+      for d in g.darts() {
+        for i in 0..=2 {
+          assert_eq!(a.map[&g[(d, i)]], g[(a.map[&d], i)]);
+        }
+      }
+    }
+
+    // a clue in a corner square can only move to other corners
+    let mut clues = crate::OrbitMap::over_cells(2);
+    clues.insert(&g, squares[0][0], 1);
+    let corners: HashSet<Dart> = [(0, 0), (0, 2), (2, 0), (2, 2)]
+      .into_iter()
+      .map(|(r, c)| squares[r][c])
+      .collect();
+    for a in &group {
+      let moved = clues.remap(&a.map);
+      let d = moved.map().keys().min().unwrap();
+      assert!(g.cell(*d, 2).any(|x| corners.contains(&x)));
+    }
+
+    assert_eq!(automorphisms(&SquareGrid::new(2, 3)).len(), 4);
+
+    let mut g = GMap::empty(2).unwrap();
+    g.add_polygon(3);
+    g.add_polygon(3);
+    assert_eq!(automorphisms(&g).len(), 72);
+
+    // Mobius strip is not orientable
+    let mut g = GMap::empty(2).unwrap();
+    let s = g.add_polygon(4);
+    g.sew(2, g.al(s, [0, 1]), g.al(s, [1, 0])).unwrap();
+    assert!(automorphism_generators(&g)
+      .iter()
+      .all(|a| a.orientation_preserving.is_none()));
+  }
+}
@@ -1,9 +1,16 @@
 #[cfg(feature = "serde")]
 mod format;
+pub mod build;
+pub mod conway;
 pub mod grids;
+pub mod isomorphism;
+pub mod mesh;
+pub mod subdivision;
+pub mod topology;
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
+use std::hash::Hash;
 use std::ops::Index;
 
 use itertools::{EitherOrBoth, Itertools};
@@ -29,6 +36,22 @@ pub enum GMapError {
   DimensionTooLarge,
   #[error("Dart is deleted")]
   Deleted,
+  #[error("Map is not orientable")]
+  NonOrientable,
+  #[error("Operation requires a map of dimension {0}")]
+  WrongDimension(usize),
+  #[error("Cell is not removable")]
+  Unremovable,
+  #[error("Cell is not contractible")]
+  Uncontractible,
+  #[error("Darts are not splittable")]
+  Unsplittable,
+  #[error("Map has an edge with only one end")]
+  OpenEdge,
+  #[error("Invalid embedding: {0}")]
+  InvalidEmbedding(String),
+  #[error("Invalid mesh: {0}")]
+  InvalidMesh(String),
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -88,11 +111,231 @@ impl Alphas {
       return Some(i - 1);
     })
   }
+
+  /// The corresponding generators in the dual of a map of the given dimension,
+  /// with alpha_i replaced by alpha_{dimension - i}.
+  pub fn dual(self, dimension: usize) -> Self {
+    let low = (1 << (dimension + 1)) - 1;
+    let reversed = (0..=dimension)
+      .filter(|&i| self.has(i))
+      .fold(0, |x, i| x | (1 << (dimension - i)));
+    Self((self.0 & !low) | reversed)
+  }
 }
 
 /// Maximum dimension allowed.  The memory requirement goes up exponentially with dimension, so 31 should be more than enough.
 pub const MAX_DIMENSION: usize = 31;
 
+/// Interface to a generalized map, independent of how it is stored.
+///
+/// Implementors provide the dimension, the darts, and the alpha involutions;
+/// orbit traversal and incidence queries are derived from those.
+/// Any implementation can be converted to the explicit representation with `to_gmap`.
+pub trait GMapLike {
+  /// Darts are compared by `Ord` to choose orbit representatives.
+  type Dart: Copy + Eq + Hash + Ord + fmt::Debug;
+
+  fn dimension(&self) -> usize;
+
+  /// Iterate over all darts in increasing order.
+  fn darts(&self) -> impl Iterator<Item = Self::Dart> + '_;
+
+  /// Apply alpha_i to d.  d is i-free if this returns d itself.
+  fn alpha(&self, d: Self::Dart, i: usize) -> Self::Dart;
+
+  fn al(&self, d: Self::Dart, indices: impl IntoIterator<Item = usize>) -> Self::Dart {
+    indices.into_iter().fold(d, |d, i| self.alpha(d, i))
+  }
+
+  fn is_free(&self, d: Self::Dart, i: usize) -> bool {
+    self.alpha(d, i) == d
+  }
+
+  /// Enumerate the a-orbit of d.
+  /// Returns an iterator returning darts together with the
+  /// index via which each dart was first reached.
+  /// The order of darts returned is deterministic based on the local topology.
+  fn orbit_indices(
+    &self,
+    d: Self::Dart,
+    a: Alphas,
+  ) -> impl Iterator<Item = (Option<usize>, Self::Dart)> + '_ {
+    if self.dimension() == 2 {
+      if let Some(x) = plane_orbit_indices(self, d, a) {
+        return x;
+      }
+    }
+
+    let mut frontier = VecDeque::with_capacity(1);
+    frontier.push_back((None, d));
+    OrbitImpl::BFS(Orbit {
+      g: self,
+      a,
+      seen: HashSet::new(),
+      frontier,
+    })
+  }
+
+  /// Iterate over the cycle obtained by repeatedly applying the alpha indices to d until it reaches d again.
+  fn cycle<'a>(
+    &'a self,
+    d: Self::Dart,
+    indices: &'a [usize],
+  ) -> impl Iterator<Item = Self::Dart> + 'a {
+    std::iter::successors(Some(d), move |&x| {
+      let x = self.al(x, indices.iter().cloned());
+      if x == d {
+        None
+      } else {
+        Some(x)
+      }
+    })
+  }
+
+  fn orbit(&self, d: Self::Dart, a: Alphas) -> impl Iterator<Item = Self::Dart> + '_ {
+    self.orbit_indices(d, a).map(|(_, d)| d)
+  }
+
+  fn cell(&self, d: Self::Dart, i: usize) -> impl Iterator<Item = Self::Dart> + '_ {
+    self.orbit(d, Alphas::cell(i))
+  }
+
+  /// filter out darts which are in the same a-orbit as a previous dart
+  fn unique_by_orbit<'a>(
+    &'a self,
+    l: impl IntoIterator<Item = Self::Dart> + 'a,
+    a: Alphas,
+  ) -> impl Iterator<Item = Self::Dart> + 'a {
+    let mut seen = HashSet::new();
+    l.into_iter().filter_map(move |dart| {
+      if seen.contains(&dart) {
+        return None;
+      }
+      for n in self.orbit(dart, a) {
+        seen.insert(n);
+      }
+      Some(dart)
+    })
+  }
+
+  /// one dart per a-orbit.
+  /// returned darts are lowest-numbered in their a-orbit.
+  fn one_dart_per_orbit(&self, a: Alphas) -> impl Iterator<Item = Self::Dart> + '_ {
+    self.unique_by_orbit(self.darts(), a)
+  }
+
+  /// one dart per i-cell.
+  /// returned darts are lowest-numbered in their i-cell.
+  fn one_dart_per_cell(&self, i: usize) -> impl Iterator<Item = Self::Dart> + '_ {
+    self.one_dart_per_orbit(Alphas::cell(i))
+  }
+
+  /// one dart per a-orbit incident to d's b-orbit.
+  /// darts are guaranteed to be in both orbits.
+  fn one_dart_per_incident_orbit(
+    &self,
+    d: Self::Dart,
+    a: Alphas,
+    b: Alphas,
+  ) -> impl Iterator<Item = Self::Dart> + '_ {
+    self.unique_by_orbit(self.orbit(d, b), a)
+  }
+
+  /// one dart per i-cell (in dim) incident to d's j-cell (in dim).
+  /// darts are guaranteed to be in both cells.
+  fn one_dart_per_incident_cell(
+    &self,
+    d: Self::Dart,
+    i: usize,
+    j: usize,
+  ) -> impl Iterator<Item = Self::Dart> + '_ {
+    self.one_dart_per_incident_orbit(d, Alphas::cell(i), Alphas::cell(j))
+  }
+
+  /// Two-color the darts of d's connected component so that darts related by any alpha_i
+  /// have different colors, with d colored true.
+  /// Each color class is one of the two orientations of the component.
+  fn orientation(&self, d: Self::Dart) -> Result<HashMap<Self::Dart, bool>, GMapError> {
+    let mut color = HashMap::new();
+    color.insert(d, true);
+    let mut stack = vec![d];
+    while let Some(x) = stack.pop() {
+      let c = color[&x];
+      for i in 0..=self.dimension() {
+        let y = self.alpha(x, i);
+        if y == x {
+          continue;
+        }
+        match color.get(&y) {
+          Some(&cy) if cy == c => return Err(GMapError::NonOrientable),
+          Some(_) => {}
+          None => {
+            color.insert(y, !c);
+            stack.push(y);
+          }
+        }
+      }
+    }
+    Ok(color)
+  }
+
+  /// one dart per connected component.
+  /// returned darts are lowest-numbered in their component.
+  fn one_dart_per_component(&self) -> impl Iterator<Item = Self::Dart> + '_ {
+    self.one_dart_per_orbit(Alphas(!0))
+  }
+
+  fn count_components(&self) -> usize {
+    self.one_dart_per_component().count()
+  }
+
+  /// Whether every connected component is orientable.
+  fn is_orientable(&self) -> bool {
+    self
+      .one_dart_per_component()
+      .all(|d| self.orientation(d).is_ok())
+  }
+
+  /// Whether in every connected component, the lower-numbered darts of all angles
+  /// (alpha_1 pairs) belong to the same orientation.
+  fn is_oriented(&self) -> bool {
+    self.one_dart_per_component().all(|d| {
+      let color = match self.orientation(d) {
+        Ok(color) => color,
+        Err(_) => return false,
+      };
+      color
+        .iter()
+        .filter(|&(&x, _)| x < self.alpha(x, 1))
+        .map(|(_, &c)| c)
+        .all_equal()
+    })
+  }
+
+  /// Convert to the explicit representation.
+  /// Darts are numbered densely in increasing order;
+  /// also returns the mapping from darts of self to darts of the result.
+  fn to_gmap(&self) -> (GMap, HashMap<Self::Dart, Dart>) {
+    GMap::induced(self, self.darts())
+  }
+}
+
+impl<G: GMapLike + ?Sized> GMapLike for &G {
+  type Dart = G::Dart;
+
+  fn dimension(&self) -> usize {
+    (**self).dimension()
+  }
+
+  fn darts(&self) -> impl Iterator<Item = G::Dart> + '_ {
+    (**self).darts()
+  }
+
+  fn alpha(&self, d: G::Dart, i: usize) -> G::Dart {
+    (**self).alpha(d, i)
+  }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(
   feature = "serde",
@@ -205,24 +448,13 @@ impl GMap {
     Ok(())
   }
 
-  #[inline(always)]
-  pub fn dimension(&self) -> usize {
-    self.dimension
-  }
-
   /// Number of darts (including deleted)
   #[inline(always)]
   fn ndarts(&self) -> usize {
     self.alpha.len() / (self.dimension + 1)
   }
 
-  pub fn darts(&self) -> impl Iterator<Item = Dart> + '_ {
-    (0..self.ndarts())
-      .map(Dart)
-      .filter(|&d| !self.is_deleted(d))
-  }
-
-  pub(crate) fn alpha(&self) -> &[Dart] {
+  pub(crate) fn alpha_slice(&self) -> &[Dart] {
     &self.alpha
   }
 
@@ -231,10 +463,134 @@ impl GMap {
     &mut self.alpha[d.0 * (self.dimension + 1) + i]
   }
 
-  pub fn al(&self, d: Dart, indices: impl IntoIterator<Item = usize>) -> Dart {
-    indices
+  /// Build the map consisting of the given darts of g, numbered densely in the given order.
+  /// Links to darts outside the set are made free.
+  /// Also returns the mapping from darts of g to darts of the result.
+  pub(crate) fn induced<G: GMapLike + ?Sized>(
+    g: &G,
+    darts: impl IntoIterator<Item = G::Dart>,
+  ) -> (GMap, HashMap<G::Dart, Dart>) {
+    let m: HashMap<G::Dart, Dart> = darts
+      .into_iter()
+      .enumerate()
+      .map(|(i, d)| (d, Dart(i)))
+      .collect();
+    let dimension = g.dimension();
+    let mut alpha = vec![Dart(0); m.len() * (dimension + 1)];
+    for (&d, &x) in m.iter() {
+      for i in 0..=dimension {
+        alpha[x.0 * (dimension + 1) + i] = m.get(&g.alpha(d, i)).cloned().unwrap_or(x);
+      }
+    }
+    let h = GMap {
+      dimension,
+      alpha,
+      deleted: vec![false; m.len()],
+    };
+    debug_assert!(h.check_valid().is_ok());
+    (h, m)
+  }
+
+  /// Component number of each dart, indexed by dart.
+  /// Components are numbered in order of their lowest-numbered darts,
+  /// as in `one_dart_per_component`.  Deleted darts are assigned `!0`.
+  pub fn component_ids(&self) -> Vec<usize> {
+    let mut ids = vec![!0; self.ndarts()];
+    for (c, d) in self.one_dart_per_component().enumerate() {
+      for x in self.orbit(d, Alphas(!0)) {
+        ids[x.0] = c;
+      }
+    }
+    ids
+  }
+
+  /// Copy the connected component of d into its own map,
+  /// keeping the relative order of darts.
+  /// Also returns the mapping from darts of self to darts of the result.
+  pub fn extract_component(&self, d: Dart) -> (GMap, HashMap<Dart, Dart>) {
+    GMap::induced(self, self.orbit(d, Alphas(!0)).sorted())
+  }
+
+  /// Copy the i-cells of the given darts into their own map, keeping the relative order of darts.
+  /// Links to darts outside those cells are made free, so that e.g. a set of faces of a 2-map
+  /// gets a boundary along the cut.
+  /// Also returns the mappings from darts of self to darts of the result, and back,
+  /// which can be used with `OrbitMap::remap` to carry data between the two.
+  pub fn submap(
+    &self,
+    i: usize,
+    cells: impl IntoIterator<Item = Dart>,
+  ) -> (GMap, HashMap<Dart, Dart>, HashMap<Dart, Dart>) {
+    let darts: HashSet<Dart> = cells
       .into_iter()
-      .fold(d, |d, a| self.alpha[d.0 * (self.dimension + 1) + a])
+      .flat_map(|d| self.cell(d, i).collect::<Vec<_>>())
+      .collect();
+    let (h, m) = GMap::induced(self, darts.into_iter().sorted());
+    let back = m.iter().map(|(&d, &x)| (x, d)).collect();
+    (h, m, back)
+  }
+
+  /// Split into one map per connected component, in order of their lowest-numbered darts.
+  pub fn split_components(&self) -> Vec<(GMap, HashMap<Dart, Dart>)> {
+    self
+      .one_dart_per_component()
+      .map(|d| self.extract_component(d))
+      .collect()
+  }
+
+  /// Renumber darts so that the lower-numbered dart in every angle (alpha_1 pair)
+  /// of d's connected component has the same orientation as d.
+  /// If d points counterclockwise in its face, this establishes the convention used by graph-folding.
+  /// Darts in other components keep their numbers.
+  /// Returns the renumbered map and the mapping from old to new darts.
+  pub fn orient(&self, d: Dart) -> Result<(GMap, HashMap<Dart, Dart>), GMapError> {
+    let color = self.orientation(d)?;
+    let perm: Vec<Dart> = (0..self.ndarts())
+      .map(Dart)
+      .map(|x| {
+        let y = self[(x, 1)];
+        if color.get(&x) == Some(&(x > y)) {
+          y
+        } else {
+          x
+        }
+      })
+      .collect();
+    let g = self.permute(&perm);
+    Ok((g, self.darts().map(|x| (x, perm[x.0])).collect()))
+  }
+
+  /// Renumber darts according to a permutation given as perm[old] = new.
+  fn permute(&self, perm: &[Dart]) -> GMap {
+    let n = self.dimension;
+    let mut alpha = vec![Dart(0); self.alpha.len()];
+    let mut deleted = vec![false; self.ndarts()];
+    for d in 0..self.ndarts() {
+      let p = perm[d].0;
+      for i in 0..=n {
+        alpha[p * (n + 1) + i] = perm[self[(Dart(d), i)].0];
+      }
+      deleted[p] = self.deleted[d];
+    }
+    GMap {
+      dimension: n,
+      alpha,
+      deleted,
+    }
+  }
+
+  /// The dual map, with alpha_i replaced by alpha_{n-i}.
+  /// Darts keep their numbering, so each i-cell of self is the same set of darts
+  /// as an (n-i)-cell of the dual, and data can be carried over with `OrbitMap::dual`.
+  pub fn dual(&self) -> GMap {
+    let n = self.dimension;
+    let mut g = self.clone();
+    for d in 0..self.ndarts() {
+      for i in 0..=n {
+        *g.al1(Dart(d), i) = self[(Dart(d), n - i)];
+      }
+    }
+    g
   }
 
   pub fn increase_dimension(&mut self, dim: usize) -> Result<(), GMapError> {
@@ -274,12 +630,17 @@ impl GMap {
     }
   }
 
-  pub(crate) fn is_deleted(&self, d: Dart) -> bool {
-    self.deleted[d.0]
+  /// Remove deleted darts, renumbering the remaining darts densely in the same order.
+  /// Returns the mapping from old to new darts,
+  /// which can be passed to `OrbitMap::remap` and `OrbitReprs::remap`.
+  pub fn compact(&mut self) -> HashMap<Dart, Dart> {
+    let (g, m) = GMap::induced(self, self.darts());
+    *self = g;
+    m
   }
 
-  pub fn is_free(&self, d: Dart, i: usize) -> bool {
-    self[(d, i)] == d
+  pub(crate) fn is_deleted(&self, d: Dart) -> bool {
+    self.deleted[d.0]
   }
 
   fn link(&mut self, i: usize, d0: Dart, d1: Dart) -> Result<(), GMapError> {
@@ -338,114 +699,303 @@ impl GMap {
     self.add_cycle(1, 0, n)
   }
 
-  fn plane_orbit_indices(&self, d: Dart, a: Alphas) -> Option<OrbitImpl<'_>> {
-    use OrbitImpl::*;
-    fn plus_one(g: &GMap, d: Dart, i: usize) -> OrbitImpl<'static> {
-      let d1 = g[(d, i)];
-      if d == d1 {
-        Array1([(None, d)].into_iter())
-      } else {
-        Array2([(None, d), (Some(i), d1)].into_iter())
+  /// Copy other into self as new connected components.
+  /// other may have lower dimension, in which case the extra alphas are left free.
+  /// Returns the mapping from darts of other to the new darts in self.
+  pub fn disjoint_union<G: GMapLike + ?Sized>(
+    &mut self,
+    other: &G,
+  ) -> Result<HashMap<G::Dart, Dart>, GMapError> {
+    if other.dimension() > self.dimension {
+      return Err(GMapError::WrongDimension(self.dimension));
+    }
+    let m: HashMap<G::Dart, Dart> = other.darts().map(|d| (d, self.add_dart())).collect();
+    for (&d, &x) in m.iter() {
+      for i in 0..=other.dimension() {
+        *self.al1(x, i) = m[&other.alpha(d, i)];
       }
     }
+    Ok(m)
+  }
 
-    match (!a.0) & 7 {
-      0 => None,
-      // vertex
-      1 => Some(Path(PathOrbit {
-        g: self,
-        i: 1,
-        j: 2,
-        start: d,
-        current: d,
-        state: PathOrbitState::Initial,
-      })),
-      // edge
-      2 => {
-        let d0 = self[(d, 0)];
-        let d2 = self[(d, 2)];
-        if d == d0 {
-          Some(plus_one(self, d, 2))
-        } else {
-          if d == d2 {
-            Some(Array2([(None, d), (Some(0), d0)].into_iter()))
-          } else {
-            Some(Array4(
-              [
-                (None, d),
-                (Some(0), d0),
-                (Some(2), d2),
-                (Some(2), self[(d0, 2)]),
-              ]
-              .into_iter(),
-            ))
+  /// The boundary path of length n starting at the 2-free dart d in a 2-map:
+  /// each dart is followed by the next 2-free dart at the other end of its edge.
+  pub fn boundary_path(&self, d: Dart, n: usize) -> Vec<Dart> {
+    std::iter::successors(Some(d), |&x| Some(topology::boundary_next(self, x)))
+      .take(n)
+      .collect()
+  }
+
+  /// Glue two boundary paths of a 2-map edge by edge, sewing path0[k] to path1[k].
+  /// If reverse is set, path1 is traversed backwards instead,
+  /// sewing path0[k] to the opposite end of path1[len - 1 - k].
+  /// Either all edges are sewn or none are.
+  /// Returns the mapping of pairs of darts which were sewn.
+  pub fn glue(
+    &mut self,
+    path0: &[Dart],
+    path1: &[Dart],
+    reverse: bool,
+  ) -> Result<HashMap<Dart, Dart>, GMapError> {
+    if self.dimension != 2 {
+      return Err(GMapError::WrongDimension(2));
+    }
+    if path0.len() != path1.len() {
+      return Err(GMapError::Unsewable);
+    }
+    let pairs: Vec<(Dart, Dart)> = if reverse {
+      path0
+        .iter()
+        .zip(path1.iter().rev())
+        .map(|(&d0, &d1)| (d0, self[(d1, 0)]))
+        .collect()
+    } else {
+      path0.iter().cloned().zip(path1.iter().cloned()).collect()
+    };
+    let mut used = HashSet::new();
+    for &(d0, d1) in &pairs {
+      for d in [d0, d1] {
+        if !self.is_free(d, 2) || !used.insert(d) || !used.insert(self[(d, 0)]) {
+          return Err(GMapError::Unsewable);
+        }
+      }
+    }
+    let mut sewn = HashMap::new();
+    for &(d0, d1) in &pairs {
+      match self.sew(2, d0, d1) {
+        Ok(m) => sewn.extend(m),
+        Err(e) => {
+          for &d in sewn.keys() {
+            self.unlink(2, d).ok();
           }
+          return Err(e);
         }
       }
-      // half-edge
-      3 => Some(plus_one(self, d, 2)),
-      // face
-      4 => Some(Path(PathOrbit {
-        g: self,
-        i: 0,
-        j: 1,
-        start: d,
-        current: d,
-        state: PathOrbitState::Initial,
-      })),
-      // angle
-      5 => Some(plus_one(self, d, 1)),
-      // side
-      6 => Some(plus_one(self, d, 0)),
-      // dart
-      7 => Some(Array1([(None, d)].into_iter())),
-      _ => unreachable!(),
     }
+    Ok(sewn)
   }
 
-  /// Enumerate the a-orbit of d.
-  /// Returns an iterator returning darts together with the
-  /// index via which each dart was first reached.
-  /// The order of darts returned is deterministic based on the local topology.
-  pub fn orbit_indices(
-    &self,
-    d: Dart,
-    a: Alphas,
-  ) -> impl Iterator<Item = (Option<usize>, Dart)> + '_ {
-    if self.dimension == 2 {
-      if let Some(x) = self.plane_orbit_indices(d, a) {
-        return x;
-      }
+  /// Cap the boundary component of a 2-map containing the 2-free dart d with a new face.
+  /// If the component is oriented, the result is too.
+  /// Returns the lowest-numbered dart of the new face.
+  pub fn cap_boundary(&mut self, d: Dart) -> Result<Dart, GMapError> {
+    if self.dimension != 2 {
+      return Err(GMapError::WrongDimension(2));
+    }
+    if !self.is_free(d, 2) {
+      return Err(GMapError::NotFree);
+    }
+    let path = self.cap_direction(topology::boundary(self, d));
+    let f = self.add_polygon(path.len());
+    let sides: Vec<Dart> = self.cycle(f, &[0, 1]).collect();
+    for (x, y) in path.into_iter().zip(sides) {
+      self.sew(2, x, y).unwrap();
     }
+    Ok(f)
+  }
 
-    let mut frontier = VecDeque::with_capacity(1);
-    frontier.push_back((None, d));
-    OrbitImpl::BFS(Orbit {
-      g: self,
-      a,
-      seen: HashSet::new(),
-      frontier,
-    })
+  /// Cap each boundary component of a 2-map with its own new face,
+  /// as for `cap_boundary`.
+  /// Returns the lowest-numbered dart of each new face,
+  /// in the order given by `topology::boundaries`.
+  pub fn cap_boundaries(&mut self) -> Result<Vec<Dart>, GMapError> {
+    topology::boundaries(self)?
+      .into_iter()
+      .map(|b| self.cap_boundary(b[0]))
+      .collect()
   }
 
-  /// Iterate over the cycle obtained by repeatedly applying the alpha indices to d until it reaches d again.
-  pub fn cycle<'a>(&'a self, d: Dart, indices: &'a [usize]) -> impl Iterator<Item = Dart> + 'a {
-    std::iter::successors(Some(d), move |&x| {
-      let x = self.al(x, indices.iter().cloned());
-      if x == d {
-        None
-      } else {
-        Some(x)
+  /// Cap all boundary components of a 2-map with a single new face,
+  /// which is connected to each boundary in turn by a new edge with the face on both sides.
+  /// Joining boundaries of different components this way
+  /// closes, for example, several disks into a single sphere,
+  /// while joining two boundaries of the same component adds a handle.
+  /// Returns the lowest-numbered dart of the new face, or None if there are no boundaries.
+  pub fn cap_boundaries_with_one_face(&mut self) -> Result<Option<Dart>, GMapError> {
+    let paths: Vec<Vec<Dart>> = topology::boundaries(self)?
+      .into_iter()
+      .map(|b| self.cap_direction(b))
+      .collect();
+    if paths.is_empty() {
+      return Ok(None);
+    }
+    // the face goes around each boundary in turn, crossing a bridge to the next,
+    // then returns across all the bridges in reverse order
+    let bridges = paths.len() - 1;
+    let n = paths.iter().map(|p| p.len()).sum::<usize>() + 2 * bridges;
+    let f = self.add_polygon(n);
+    let sides: Vec<Dart> = self.cycle(f, &[0, 1]).collect();
+    let mut k = 0;
+    let mut bridge_starts = vec![];
+    for (b, path) in paths.into_iter().enumerate() {
+      if b > 0 {
+        bridge_starts.push(sides[k]);
+        k += 1;
       }
-    })
+      for x in path {
+        self.sew(2, x, sides[k]).unwrap();
+        k += 1;
+      }
+    }
+    for s in bridge_starts.into_iter().rev() {
+      self.sew(2, s, self[(sides[k], 0)]).unwrap();
+      k += 1;
+    }
+    Ok(Some(f))
+  }
+
+  /// Direct a boundary cycle so that it starts from the higher-numbered dart in its 1-link,
+  /// so that the cap sewn to it is oriented consistently with the rest of the component.
+  fn cap_direction(&self, path: Vec<Dart>) -> Vec<Dart> {
+    let d = path[0];
+    if d < self[(d, 1)] {
+      let d = self[(path[path.len() - 1], 0)];
+      topology::boundary(self, d)
+    } else {
+      path
+    }
   }
 
-  pub fn orbit(&self, d: Dart, a: Alphas) -> impl Iterator<Item = Dart> + '_ {
-    self.orbit_indices(d, a).into_iter().map(|(_, d)| d)
+  /// Remove the i-cell at d, merging the (i+1)-cells on either side of it.
+  /// For example in a 2-map, 1-removal merges the faces on either side of an edge,
+  /// and 0-removal merges the two edges at a vertex of degree 2.
+  /// The cell must have degree at most 2, that is, alpha_{i+1} and alpha_{i+2}
+  /// must commute on its darts.
+  /// The darts of the cell are deleted.
+  pub fn remove(&mut self, i: usize, d: Dart) -> Result<(), GMapError> {
+    if i >= self.dimension {
+      return Err(GMapError::Unremovable);
+    }
+    self
+      .remove_or_contract(i, i + 1, d)
+      .map_err(|e| e.unwrap_or(GMapError::Unremovable))
+  }
+
+  /// Contract the i-cell at d, merging the (i-1)-cells on either side of it.
+  /// This is dual to i-removal: for example in a 2-map, 1-contraction merges
+  /// the endpoints of an edge, and 2-contraction merges the two edges of a digon.
+  /// The cell must have codegree at most 2, that is, alpha_{i-1} and alpha_{i-2}
+  /// must commute on its darts.
+  /// The darts of the cell are deleted.
+  pub fn contract(&mut self, i: usize, d: Dart) -> Result<(), GMapError> {
+    if i == 0 || i > self.dimension {
+      return Err(GMapError::Uncontractible);
+    }
+    self
+      .remove_or_contract(i, i - 1, d)
+      .map_err(|e| e.unwrap_or(GMapError::Uncontractible))
   }
 
-  pub fn cell(&self, d: Dart, i: usize) -> impl Iterator<Item = Dart> + '_ {
-    self.orbit(d, Alphas::cell(i))
+  /// Merge the two faces on either side of the edge at d.
+  pub fn remove_edge(&mut self, d: Dart) -> Result<(), GMapError> {
+    self.remove(1, d)
+  }
+
+  /// Merge the two endpoints of the edge at d.
+  pub fn contract_edge(&mut self, d: Dart) -> Result<(), GMapError> {
+    self.contract(1, d)
+  }
+
+  /// Delete the i-cell at d, reconnecting alpha_i around it by following alpha_j.
+  /// Errors with None if the degree condition fails.
+  fn remove_or_contract(&mut self, i: usize, j: usize, d: Dart) -> Result<(), Option<GMapError>> {
+    if self.is_deleted(d) {
+      return Err(Some(GMapError::Deleted));
+    }
+    let cell: HashSet<Dart> = self.cell(d, i).collect();
+    // the index two steps away from i, if any
+    let k = (2 * j).checked_sub(i).filter(|&k| k <= self.dimension);
+    if let Some(k) = k {
+      if cell
+        .iter()
+        .any(|&x| self.al(x, [j, k]) != self.al(x, [k, j]))
+      {
+        return Err(None);
+      }
+    }
+    let mut links = vec![];
+    for &x in &cell {
+      let outside = self[(x, i)];
+      if cell.contains(&outside) {
+        continue;
+      }
+      let mut z = self[(x, j)];
+      let y = loop {
+        let y = self[(z, i)];
+        if y == z {
+          break outside;
+        }
+        if !cell.contains(&y) {
+          break y;
+        }
+        z = self[(y, j)];
+      };
+      links.push((outside, y));
+    }
+    for (x, y) in links {
+      *self.al1(x, i) = y;
+    }
+    for &x in &cell {
+      *self.al1(x, i) = x;
+      self.deleted[x.0] = true;
+    }
+    Ok(())
+  }
+
+  /// Insert a new vertex in the middle of the edge at d.
+  /// Returns the dart of the new vertex which is 0-linked to d.
+  pub fn split_edge(&mut self, d: Dart) -> Result<Dart, GMapError> {
+    if self.is_deleted(d) {
+      return Err(GMapError::Deleted);
+    }
+    let edge: Vec<Dart> = self.cell(d, 1).collect();
+    let new: HashMap<Dart, Dart> = edge.iter().map(|&x| (x, self.add_dart())).collect();
+    for &x in &edge {
+      let y = new[&x];
+      let x0 = self[(x, 0)];
+      if x0 != x {
+        *self.al1(y, 1) = new[&x0];
+      }
+      for i in 2..=self.dimension {
+        *self.al1(y, i) = new[&self[(x, i)]];
+      }
+    }
+    for &x in &edge {
+      *self.al1(x, 0) = new[&x];
+      *self.al1(new[&x], 0) = x;
+    }
+    Ok(new[&d])
+  }
+
+  /// Split a face of a 2-map by inserting a new edge from the vertex of d0
+  /// to the vertex of d1.
+  /// d1 must be reachable from d0 by alternately applying alpha_0 and alpha_1,
+  /// so that both darts are on the same side of the face, and not equal to d0.
+  /// The face containing d0 keeps d0 and alpha_1(d1),
+  /// while the new face gets d1 and alpha_1(d0).
+  /// Returns the new dart 1-linked to d0.
+  pub fn split_face(&mut self, d0: Dart, d1: Dart) -> Result<Dart, GMapError> {
+    if self.dimension != 2 {
+      return Err(GMapError::WrongDimension(2));
+    }
+    if self.is_deleted(d0) || self.is_deleted(d1) {
+      return Err(GMapError::Deleted);
+    }
+    let (e0, e1) = (self[(d0, 1)], self[(d1, 1)]);
+    if d0 == d1 || e0 == d0 || e1 == d1 || !self.cycle(d0, &[0, 1]).any(|x| x == d1) {
+      return Err(GMapError::Unsplittable);
+    }
+    let p0 = self.add_edge();
+    let p1 = self[(p0, 0)];
+    let q0 = self.add_edge();
+    let q1 = self[(q0, 0)];
+    for (x, y) in [(d0, p0), (e1, p1), (e0, q0), (d1, q1)] {
+      *self.al1(x, 1) = y;
+      *self.al1(y, 1) = x;
+    }
+    self.link(2, p0, q0).unwrap();
+    self.link(2, p1, q1).unwrap();
+    Ok(p0)
   }
 
   /// Sew the i-cell at d0 to the i-cell at d1.
@@ -500,57 +1050,25 @@ impl GMap {
     }
     Ok(to_unsew)
   }
+}
 
-  /// filter out darts which are in the same a-orbit as a previous dart
-  pub fn unique_by_orbit<'a>(
-    &'a self,
-    l: impl IntoIterator<Item = Dart> + 'a,
-    a: Alphas,
-  ) -> impl Iterator<Item = Dart> + 'a {
-    let mut seen = HashSet::new();
-    l.into_iter().filter_map(move |dart| {
-      if seen.contains(&dart) {
-        return None;
-      }
-      for n in self.orbit(dart, a) {
-        seen.insert(n);
-      }
-      Some(dart)
-    })
-  }
+impl GMapLike for GMap {
+  type Dart = Dart;
 
-  /// one dart per a-orbit.
-  /// returned darts are lowest-numbered in their a-orbit.
-  pub fn one_dart_per_orbit<'a>(&'a self, a: Alphas) -> impl Iterator<Item = Dart> + 'a {
-    self.unique_by_orbit(self.darts(), a)
-  }
-
-  /// one dart per i-cell.
-  /// returned darts are lowest-numbered in their i-cell.
-  pub fn one_dart_per_cell<'a>(&'a self, i: usize) -> impl Iterator<Item = Dart> + 'a {
-    self.one_dart_per_orbit(Alphas::cell(i))
+  #[inline(always)]
+  fn dimension(&self) -> usize {
+    self.dimension
   }
 
-  /// one dart per a-orbit incident to d's b-orbit.
-  /// darts are guaranteed to be in both orbits.
-  pub fn one_dart_per_incident_orbit<'a>(
-    &'a self,
-    d: Dart,
-    a: Alphas,
-    b: Alphas,
-  ) -> impl Iterator<Item = Dart> + 'a {
-    self.unique_by_orbit(self.orbit(d, b), a)
+  fn darts(&self) -> impl Iterator<Item = Dart> + '_ {
+    (0..self.ndarts())
+      .map(Dart)
+      .filter(|&d| !self.is_deleted(d))
   }
 
-  /// one dart per i-cell (in dim) incident to d's j-cell (in dim).
-  /// darts are guaranteed to be in both cells.
-  pub fn one_dart_per_incident_cell<'a>(
-    &'a self,
-    d: Dart,
-    i: usize,
-    j: usize,
-  ) -> impl Iterator<Item = Dart> + 'a {
-    self.one_dart_per_incident_orbit(d, Alphas::cell(i), Alphas::cell(j))
+  #[inline(always)]
+  fn alpha(&self, d: Dart, i: usize) -> Dart {
+    self[(d, i)]
   }
 }
 
@@ -575,15 +1093,15 @@ impl fmt::Debug for GMap {
   }
 }
 
-struct Orbit<'a> {
-  g: &'a GMap,
+struct Orbit<'a, G: GMapLike + ?Sized> {
+  g: &'a G,
   a: Alphas,
-  seen: HashSet<Dart>,
-  frontier: VecDeque<(Option<usize>, Dart)>,
+  seen: HashSet<G::Dart>,
+  frontier: VecDeque<(Option<usize>, G::Dart)>,
 }
 
-impl Iterator for Orbit<'_> {
-  type Item = (Option<usize>, Dart);
+impl<G: GMapLike + ?Sized> Iterator for Orbit<'_, G> {
+  type Item = (Option<usize>, G::Dart);
 
   fn next(&mut self) -> Option<Self::Item> {
     let (from, dart) = loop {
@@ -604,13 +1122,83 @@ impl Iterator for Orbit<'_> {
       if !self.a.has(i) {
         continue;
       }
-      let neighbor = self.g[(dart, i)];
+      let neighbor = self.g.alpha(dart, i);
       self.frontier.push_back((Some(i), neighbor));
     }
     return Some((from, dart));
   }
 }
 
+/// Specialized orbit traversal for the common orbits of a 2-map.
+fn plane_orbit_indices<G: GMapLike + ?Sized>(
+  g: &G,
+  d: G::Dart,
+  a: Alphas,
+) -> Option<OrbitImpl<'_, G>> {
+  use OrbitImpl::*;
+  fn plus_one<G: GMapLike + ?Sized>(g: &G, d: G::Dart, i: usize) -> OrbitImpl<'_, G> {
+    let d1 = g.alpha(d, i);
+    if d == d1 {
+      Array1([(None, d)].into_iter())
+    } else {
+      Array2([(None, d), (Some(i), d1)].into_iter())
+    }
+  }
+
+  match (!a.0) & 7 {
+    0 => None,
+    // vertex
+    1 => Some(Path(PathOrbit {
+      g,
+      i: 1,
+      j: 2,
+      start: d,
+      current: d,
+      state: PathOrbitState::Initial,
+    })),
+    // edge
+    2 => {
+      let d0 = g.alpha(d, 0);
+      let d2 = g.alpha(d, 2);
+      if d == d0 {
+        Some(plus_one(g, d, 2))
+      } else {
+        if d == d2 {
+          Some(Array2([(None, d), (Some(0), d0)].into_iter()))
+        } else {
+          Some(Array4(
+            [
+              (None, d),
+              (Some(0), d0),
+              (Some(2), d2),
+              (Some(2), g.alpha(d0, 2)),
+            ]
+            .into_iter(),
+          ))
+        }
+      }
+    }
+    // half-edge
+    3 => Some(plus_one(g, d, 2)),
+    // face
+    4 => Some(Path(PathOrbit {
+      g,
+      i: 0,
+      j: 1,
+      start: d,
+      current: d,
+      state: PathOrbitState::Initial,
+    })),
+    // angle
+    5 => Some(plus_one(g, d, 1)),
+    // side
+    6 => Some(plus_one(g, d, 0)),
+    // dart
+    7 => Some(Array1([(None, d)].into_iter())),
+    _ => unreachable!(),
+  }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PathOrbitState {
   Initial,
@@ -621,26 +1209,26 @@ enum PathOrbitState {
   Done,
 }
 
-struct PathOrbit<'a> {
-  g: &'a GMap,
+struct PathOrbit<'a, G: GMapLike + ?Sized> {
+  g: &'a G,
   i: usize,
   j: usize,
-  start: Dart,
-  current: Dart,
+  start: G::Dart,
+  current: G::Dart,
   state: PathOrbitState,
 }
 
-impl Iterator for PathOrbit<'_> {
-  type Item = (Option<usize>, Dart);
+impl<G: GMapLike + ?Sized> Iterator for PathOrbit<'_, G> {
+  type Item = (Option<usize>, G::Dart);
 
   fn next(&mut self) -> Option<Self::Item> {
     use PathOrbitState::*;
 
     if self.state == Initial {
-      self.current = self.g[(self.start, self.i)];
+      self.current = self.g.alpha(self.start, self.i);
       self.state = ForwardJ;
       if self.current == self.start {
-        self.current = self.g[(self.start, self.j)];
+        self.current = self.g.alpha(self.start, self.j);
         self.state = BackwardI;
       }
       return Some((None, self.start));
@@ -661,7 +1249,7 @@ impl Iterator for PathOrbit<'_> {
     };
     let result = Some((Some(prev_index), old));
 
-    self.current = self.g[(old, current_index)];
+    self.current = self.g.alpha(old, current_index);
     self.state = match self.state {
       ForwardI => ForwardJ,
       ForwardJ => ForwardI,
@@ -673,7 +1261,7 @@ impl Iterator for PathOrbit<'_> {
     if self.current == old {
       match self.state {
         ForwardI | ForwardJ => {
-          self.current = self.g[(self.start, self.j)];
+          self.current = self.g.alpha(self.start, self.j);
           self.state = BackwardI;
         }
         _ => {
@@ -686,16 +1274,16 @@ impl Iterator for PathOrbit<'_> {
   }
 }
 
-enum OrbitImpl<'a> {
-  BFS(Orbit<'a>),
-  Path(PathOrbit<'a>),
-  Array1(std::array::IntoIter<(Option<usize>, Dart), 1>),
-  Array2(std::array::IntoIter<(Option<usize>, Dart), 2>),
-  Array4(std::array::IntoIter<(Option<usize>, Dart), 4>),
+enum OrbitImpl<'a, G: GMapLike + ?Sized> {
+  BFS(Orbit<'a, G>),
+  Path(PathOrbit<'a, G>),
+  Array1(std::array::IntoIter<(Option<usize>, G::Dart), 1>),
+  Array2(std::array::IntoIter<(Option<usize>, G::Dart), 2>),
+  Array4(std::array::IntoIter<(Option<usize>, G::Dart), 4>),
 }
 
-impl Iterator for OrbitImpl<'_> {
-  type Item = (Option<usize>, Dart);
+impl<G: GMapLike + ?Sized> Iterator for OrbitImpl<'_, G> {
+  type Item = (Option<usize>, G::Dart);
 
   fn next(&mut self) -> Option<Self::Item> {
     use OrbitImpl::*;
@@ -710,16 +1298,18 @@ impl Iterator for OrbitImpl<'_> {
 }
 
 /// Map from orbits to A.  Duplicates its values once for each dart in the orbit.
+/// D is the dart type of the map the orbits are taken in.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone)]
-pub struct OrbitMap<A> {
-  map: HashMap<Dart, A>,
+pub struct OrbitMap<A, D: Eq + Hash = Dart> {
+  map: HashMap<D, A>,
   indices: Alphas,
 }
 
-impl<A> OrbitMap<A>
+impl<A, D> OrbitMap<A, D>
 where
   A: Clone,
+  D: Copy + Eq + Hash,
 {
   pub fn new(indices: Alphas) -> Self {
     Self {
@@ -732,11 +1322,11 @@ where
     Self::new(Alphas::cell(i))
   }
 
-  pub fn map(&self) -> &HashMap<Dart, A> {
+  pub fn map(&self) -> &HashMap<D, A> {
     &self.map
   }
 
-  pub fn into_map(self) -> HashMap<Dart, A> {
+  pub fn into_map(self) -> HashMap<D, A> {
     self.map
   }
 
@@ -744,13 +1334,45 @@ where
     self.indices
   }
 
-  pub fn insert(&mut self, g: &GMap, k: Dart, v: A) {
+  /// Reinterpret this map over the dual of a map of the given dimension.
+  /// Since duals share darts, the values are unchanged and only the orbits are renamed;
+  /// e.g. a map over vertices becomes a map over faces of the dual of a 2-map.
+  pub fn dual(self, dimension: usize) -> Self {
+    Self {
+      map: self.map,
+      indices: self.indices.dual(dimension),
+    }
+  }
+
+  /// Map over the same orbits, with f applied to each value.
+  pub fn map_values<B>(&self, mut f: impl FnMut(&A) -> B) -> OrbitMap<B, D> {
+    OrbitMap {
+      map: self.map.iter().map(|(&d, v)| (d, f(v))).collect(),
+      indices: self.indices,
+    }
+  }
+
+  /// Carry the values over to other darts, moving the value at each dart d to m[d].
+  /// m should map a-orbits onto a-orbits, as automorphisms and renumberings do.
+  /// Values at darts missing from m are dropped.
+  pub fn remap<E: Copy + Eq + Hash>(&self, m: &HashMap<D, E>) -> OrbitMap<A, E> {
+    OrbitMap {
+      map: self
+        .map
+        .iter()
+        .filter_map(|(d, v)| m.get(d).map(|&e| (e, v.clone())))
+        .collect(),
+      indices: self.indices,
+    }
+  }
+
+  pub fn insert<G: GMapLike<Dart = D> + ?Sized>(&mut self, g: &G, k: D, v: A) {
     for n in g.orbit(k, self.indices) {
       self.map.insert(n, v.clone());
     }
   }
 
-  pub fn remove(&mut self, g: &GMap, k: Dart) -> Option<A> {
+  pub fn remove<G: GMapLike<Dart = D> + ?Sized>(&mut self, g: &G, k: D) -> Option<A> {
     g.orbit(k, self.indices)
       .fold(None, |v, n| v.or(self.map.remove(&n)))
   }
@@ -771,6 +1393,27 @@ impl OrbitReprs {
     self.0.get(&a).map(|v| v[d.0])
   }
 
+  /// Renumber darts according to m, as returned by `GMap::compact`.
+  /// m must preserve the order of darts, so that representatives remain lowest-numbered.
+  /// Darts missing from m are dropped,
+  /// and an orbit whose representative was dropped is represented by its lowest remaining dart.
+  pub fn remap(&mut self, m: &HashMap<Dart, Dart>) {
+    let n = m.values().map(|d| d.0 + 1).max().unwrap_or(0);
+    for v in self.0.values_mut() {
+      // the lowest new dart of each orbit, by old representative
+      let mut lowest: HashMap<Dart, Dart> = HashMap::new();
+      for (&old, &new) in m {
+        let r = lowest.entry(v[old.0]).or_insert(new);
+        *r = (*r).min(new);
+      }
+      let mut w = vec![Dart(!0); n];
+      for (&old, &new) in m {
+        w[new.0] = lowest[&v[old.0]];
+      }
+      *v = w;
+    }
+  }
+
   pub fn get_or_search(&self, g: &GMap, a: Alphas, d: Dart) -> Dart {
     if let Some(r) = self.get(a, d) {
       return r;
@@ -1047,6 +1690,9 @@ mod tests {
     m.insert(&g, Dart(0), 1);
     m.insert(&g, Dart(4), 2);
     m.insert(&g, Dart(7), 3);
+    let doubled = m.map_values(|&v| 2 * v);
+    assert_eq!(doubled.indices(), m.indices());
+    assert_eq!(doubled.map()[&Dart(4)], 4);
 
     let m: Vec<(Dart, usize)> = m.into_map().into_iter().sorted().collect();
     let expected: Vec<(Dart, usize)> = [(0, 3), (3, 2), (4, 2), (5, 3), (7, 3), (8, 3)]
@@ -1055,4 +1701,362 @@ mod tests {
       .collect();
     assert_eq!(m, expected);
   }
+
+  /// n-gon whose darts are (side, end) pairs, with alpha computed on the fly
+  struct Polygon(usize);
+
+  impl GMapLike for Polygon {
+    type Dart = (usize, bool);
+
+    fn dimension(&self) -> usize {
+      2
+    }
+
+    fn darts(&self) -> impl Iterator<Item = (usize, bool)> + '_ {
+      (0..self.0).flat_map(|s| [(s, false), (s, true)])
+    }
+
+    fn alpha(&self, (s, e): (usize, bool), i: usize) -> (usize, bool) {
+      match (i, e) {
+        (0, _) => (s, !e),
+        (1, false) => ((s + self.0 - 1) % self.0, true),
+        (1, true) => ((s + 1) % self.0, false),
+        _ => (s, e),
+      }
+    }
+  }
+
+  #[test]
+  fn test_implicit_gmap() {
+    let p = Polygon(5);
+    assert_eq!(p.orbit((0, false), Alphas::FACE).count(), 10);
+    assert_eq!(p.one_dart_per_cell(0).count(), 5);
+    assert_eq!(p.one_dart_per_cell(1).count(), 5);
+    assert!(p.is_free((3, true), 2));
+    assert_eq!(p.al((0, false), [0, 1, 0, 1]), (2, false));
+
+    let mut m: OrbitMap<usize, (usize, bool)> = OrbitMap::over_cells(0);
+    m.insert(&p, (0, false), 7);
+    assert_eq!(m.map()[&(4, true)], 7);
+
+    let (g, to_g) = p.to_gmap();
+    assert_eq!(g.darts().count(), 10);
+    assert_eq!(g.one_dart_per_cell(0).count(), 5);
+    for d in p.darts() {
+      for i in 0..=2 {
+        assert_eq!(to_g[&p.alpha(d, i)], g[(to_g[&d], i)]);
+      }
+    }
+  }
+
+  #[test]
+  fn test_dual() {
+    let g = diagonal_cp_example();
+    let h = g.dual();
+    h.check_valid().unwrap();
+    assert_eq!(h.dual().alpha, g.alpha);
+    for (i, j) in [(0, 2), (1, 1), (2, 0)] {
+      let cells: Vec<Dart> = g.one_dart_per_cell(i).collect();
+      let dual_cells: Vec<Dart> = h.one_dart_per_cell(j).collect();
+      assert_eq!(cells, dual_cells);
+    }
+    assert_eq!(Alphas::VERTEX.dual(2), Alphas::FACE);
+    assert_eq!(Alphas::EDGE.dual(2), Alphas::EDGE);
+    assert_eq!(Alphas::ANGLE.dual(2), Alphas::ANGLE);
+    assert_eq!(Alphas::HALF_EDGE.dual(2), Alphas::SIDE);
+
+    let mut m: OrbitMap<usize> = OrbitMap::over_cells(0);
+    m.insert(&g, Dart(0), 1);
+    let mut m = m.dual(2);
+    assert_eq!(m.indices(), Alphas::FACE);
+    assert_eq!(m.remove(&h, Dart(5)), Some(1));
+    assert!(m.map().is_empty());
+  }
+
+  #[test]
+  fn test_orientation() {
+    let g = diagonal_cp_example();
+    assert!(g.is_orientable());
+    assert!(!g.is_oriented());
+    let color = g.orientation(Dart(0)).unwrap();
+    assert_eq!(color.len(), 12);
+    for d in g.darts() {
+      for i in 0..=2 {
+        if !g.is_free(d, i) {
+          assert_ne!(color[&d], color[&g[(d, i)]]);
+        }
+      }
+    }
+
+    let (h, m) = g.orient(Dart(0)).unwrap();
+    h.check_valid().unwrap();
+    assert!(h.is_oriented());
+    assert_eq!(m[&Dart(0)], Dart(0));
+    assert_eq!(m[&Dart(1)], Dart(2));
+    assert_eq!(m[&Dart(2)], Dart(1));
+    for d in g.darts() {
+      for i in 0..=2 {
+        assert_eq!(m[&g[(d, i)]], h[(m[&d], i)]);
+      }
+    }
+
+    // square with two opposite sides glued: a cylinder, then a Mobius strip
+    let mut g = GMap::empty(2).unwrap();
+    let s = g.add_polygon(4);
+    assert!(g.is_oriented());
+    g.sew(2, g.al(s, [0, 1]), g.al(s, [1])).unwrap();
+    assert!(g.is_orientable());
+    assert!(g.is_oriented());
+    let mut g = GMap::empty(2).unwrap();
+    let s = g.add_polygon(4);
+    g.sew(2, g.al(s, [0, 1]), g.al(s, [1, 0])).unwrap();
+    assert!(!g.is_orientable());
+    assert!(!g.is_oriented());
+    g.orient(s).unwrap_err();
+  }
+
+  #[test]
+  fn test_components() {
+    let mut g = diagonal_cp_example();
+    let t = g.add_polygon(3);
+    let s = g.add_polygon(4);
+    assert_eq!(g.count_components(), 3);
+    assert_eq!(
+      g.one_dart_per_component().collect::<Vec<_>>(),
+      vec![Dart(0), t, s]
+    );
+    let ids = g.component_ids();
+    assert_eq!(ids[5], 0);
+    assert_eq!(ids[t.0 + 5], 1);
+    assert_eq!(ids[s.0 + 7], 2);
+
+    let (h, m) = g.extract_component(g.al(s, [0, 1]));
+    h.check_valid().unwrap();
+    assert_eq!(h.darts().count(), 8);
+    assert_eq!(m[&s], Dart(0));
+    assert_eq!(h.one_dart_per_cell(0).count(), 4);
+
+    g.delete(t);
+    assert_eq!(g.component_ids()[t.0], !0);
+    let parts = g.split_components();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].0.alpha, diagonal_cp_example().alpha);
+    assert_eq!(parts[1].1[&s], Dart(0));
+  }
+
+  #[test]
+  fn test_submap() {
+    let (g, rows) = grids::square::new(3, 3);
+    let block = [rows[0][0], rows[0][1], rows[1][0], rows[1][1]];
+    let (h, m, back) = g.submap(2, block);
+    h.check_valid().unwrap();
+    assert_eq!(h.darts().count(), 4 * 8);
+    assert_eq!(m.len(), back.len());
+    assert!(m.iter().all(|(d, x)| back[x] == *d));
+    assert_eq!(m[&rows[0][0]], Dart(0));
+    let c = topology::components(&h).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (9, 12, 4));
+    assert_eq!(c[0].surface(), Some(topology::Surface::DISK));
+    // the cut is along the edges between the block and the rest of the grid
+    let cut = rows[0][1];
+    let cut = g.al(cut, [0, 1]);
+    assert!(!g.is_free(cut, 2));
+    assert!(h.is_free(m[&cut], 2));
+
+    // carry vertex data into the submap and back
+    let mut values: OrbitMap<usize> = OrbitMap::over_cells(0);
+    for (k, v) in g.one_dart_per_cell(0).enumerate() {
+      values.insert(&g, v, k);
+    }
+    let sub = values.remap(&m);
+    for x in h.darts() {
+      assert_eq!(sub.map()[&x], values.map()[&back[&x]]);
+    }
+    let pushed = sub.remap(&back);
+    assert_eq!(pushed.map().len(), 4 * 8);
+    assert!(pushed.map().iter().all(|(d, k)| values.map()[d] == *k));
+
+    // an interior edge on its own, with both of its sides
+    let (h, _, _) = g.submap(1, [rows[1][1]]);
+    assert_eq!(h.darts().count(), 4);
+    assert_eq!(h.one_dart_per_cell(1).count(), 1);
+  }
+
+  #[test]
+  fn test_compact() {
+    let mut g = GMap::empty(2).unwrap();
+    let t = g.add_polygon(3);
+    let s = g.add_polygon(4);
+    let mut m: OrbitMap<usize> = OrbitMap::over_cells(0);
+    m.insert(&g, g.al(s, [0]), 1);
+    let mut or = OrbitReprs::new();
+    or.build(&g, Alphas::EDGE);
+
+    g.delete(t);
+    let renumber = g.compact();
+    g.check_valid().unwrap();
+    assert_eq!(g.ndarts(), 8);
+    assert_eq!(renumber.len(), 8);
+    assert_eq!(renumber[&s], Dart(0));
+    assert!(!renumber.contains_key(&t));
+
+    let m = m.remap(&renumber);
+    assert_eq!(m.map().len(), 2);
+    assert_eq!(m.map()[&g.al(Dart(0), [0])], 1);
+    or.remap(&renumber);
+    assert_eq!(or.get_all(Alphas::EDGE).unwrap().len(), 8);
+    assert_eq!(or[(Alphas::EDGE, g.al(Dart(0), [0]))], Dart(0));
+
+    // removing the edge between two squares drops the representatives of its vertices
+    let (mut g, squares) = grids::square::new(1, 2);
+    let mut or = OrbitReprs::new();
+    or.build(&g, Alphas::VERTEX);
+    let e = g.al(squares[0][0], [0, 1]);
+    assert!(g.cell(e, 1).any(|x| or[(Alphas::VERTEX, x)] == x));
+    g.remove_edge(e).unwrap();
+    let renumber = g.compact();
+    or.remap(&renumber);
+    for d in g.darts() {
+      assert_eq!(or[(Alphas::VERTEX, d)], g.cell(d, 0).min().unwrap());
+    }
+  }
+
+  #[test]
+  fn test_union_and_glue() {
+    let (h, squares) = grids::square::new(2, 3);
+    let mut g = GMap::empty(2).unwrap();
+    let m0 = g.disjoint_union(&h).unwrap();
+    let m1 = g.disjoint_union(&h).unwrap();
+    g.check_valid().unwrap();
+    assert_eq!(g.count_components(), 2);
+
+    let west = |m: &HashMap<Dart, Dart>| g.boundary_path(g.al(m[&squares[0][0]], [1]), 2);
+    let east = |m: &HashMap<Dart, Dart>| g.boundary_path(g.al(m[&squares[0][2]], [0, 1]), 2);
+    let (w0, e0, w1, e1) = (west(&m0), east(&m0), west(&m1), east(&m1));
+    assert_eq!(w0[1], g.al(m0[&squares[1][0]], [1]));
+
+    // paths of different lengths, or overlapping paths, are rejected without sewing anything
+    g.glue(&w0, &e0[..1], false).unwrap_err();
+    g.glue(&w0, &[w0[1], e0[0]], false).unwrap_err();
+    assert!(g.is_free(w0[0], 2));
+
+    let sewn = g.glue(&w0, &e0, false).unwrap();
+    assert_eq!(sewn.len(), 4);
+    g.glue(&w1, &e1, true).unwrap();
+    let c = topology::components(&g).unwrap();
+    assert_eq!(c[0].surface(), Some(topology::Surface::CYLINDER));
+    assert_eq!(c[1].surface(), Some(topology::Surface::MOBIUS_STRIP));
+
+    let mut g = GMap::empty(1).unwrap();
+    g.disjoint_union(&h).unwrap_err();
+  }
+
+  #[test]
+  fn test_euler_operators() {
+    let (mut g, squares) = grids::square::new(2, 2);
+    let chi = |g: &GMap| topology::components(g).unwrap()[0].euler_characteristic();
+    let s = squares[0][0];
+
+    // cut the top-left square along its diagonal, then remove the cut again
+    let e = g.split_face(s, g.al(s, [0, 1, 0, 1])).unwrap();
+    g.check_valid().unwrap();
+    assert_eq!(g.one_dart_per_cell(2).count(), 5);
+    assert_eq!(g.cycle(s, &[0, 1]).count(), 3);
+    assert_eq!(chi(&g), 1);
+    g.split_face(s, g[(s, 0)]).unwrap_err();
+    g.remove_edge(e).unwrap();
+    g.check_valid().unwrap();
+    assert_eq!(g.cycle(s, &[0, 1]).count(), 4);
+
+    // subdivide an interior edge, then merge the halves back together
+    let v = g.split_edge(g.al(s, [0, 1])).unwrap();
+    g.check_valid().unwrap();
+    assert_eq!(g.cycle(s, &[0, 1]).count(), 5);
+    assert_eq!(g.cell(v, 0).count(), 4);
+    assert_eq!(chi(&g), 1);
+    // the central vertex has degree 4
+    let center = g.darts().find(|&x| g.cell(x, 0).count() == 8).unwrap();
+    g.remove(0, center).unwrap_err();
+    g.remove(0, v).unwrap();
+    g.check_valid().unwrap();
+    assert!(isomorphism::is_isomorphic(
+      &g,
+      &grids::square::SquareGrid::new(2, 2)
+    ));
+
+    // contracting an edge of a square leaves a triangle
+    let t = g[(s, 1)];
+    g.contract_edge(s).unwrap();
+    g.check_valid().unwrap();
+    assert_eq!(g.cycle(t, &[0, 1]).count(), 3);
+    assert_eq!(g.one_dart_per_cell(0).count(), 8);
+    assert_eq!(chi(&g), 1);
+    g.compact();
+    g.check_valid().unwrap();
+
+    // contracting a face of a closed 3-map
+    let mut g = GMap::empty(3).unwrap();
+    let d = g.add_polygon(2);
+    let e = g.add_polygon(2);
+    g.sew(2, d, e).unwrap();
+    g.sew(2, g[(d, 1)], g[(e, 1)]).unwrap();
+    g.contract(2, d).unwrap();
+    g.check_valid().unwrap();
+    assert_eq!(g.darts().count(), 4);
+    assert_eq!(g.one_dart_per_cell(1).count(), 1);
+    g.contract(0, e).unwrap_err();
+  }
+
+  #[test]
+  fn test_cap_boundaries() {
+    use topology::Surface;
+    let surfaces = |g: &GMap| -> Vec<Surface> {
+      topology::components(g)
+        .unwrap()
+        .iter()
+        .map(|c| c.surface().unwrap())
+        .collect()
+    };
+
+    let (mut g, squares) = grids::square::new(2, 3);
+    assert!(g.is_oriented());
+    let b = topology::boundaries(&g).unwrap();
+    assert_eq!(b.len(), 1);
+    assert_eq!(b[0].len(), 10);
+    g.cap_boundary(g.al(squares[0][0], [0, 1])).unwrap_err();
+    let f = g.cap_boundaries().unwrap();
+    assert_eq!(f.len(), 1);
+    assert_eq!(g.cycle(f[0], &[0, 1]).count(), 10);
+    assert_eq!(surfaces(&g), [Surface::SPHERE]);
+    assert!(g.is_oriented());
+    assert!(topology::boundaries(&g).unwrap().is_empty());
+
+    // a cylinder closes to a sphere with two caps, or a torus with one
+    let mut g = GMap::empty(2).unwrap();
+    let s = g.add_polygon(4);
+    g.sew(2, g.al(s, [0, 1]), g.al(s, [1])).unwrap();
+    let mut h = g.clone();
+    assert_eq!(g.cap_boundaries().unwrap().len(), 2);
+    assert_eq!(surfaces(&g), [Surface::SPHERE]);
+    assert!(h.cap_boundaries_with_one_face().unwrap().is_some());
+    assert_eq!(surfaces(&h), [Surface::TORUS]);
+    assert_eq!(h.cap_boundaries_with_one_face().unwrap(), None);
+
+    // separate disks close to a single sphere with one face
+    let mut g = GMap::empty(2).unwrap();
+    g.add_polygon(3);
+    g.add_polygon(4);
+    g.add_polygon(5);
+    let f = g.cap_boundaries_with_one_face().unwrap().unwrap();
+    assert_eq!(g.cycle(f, &[0, 1]).count(), 3 + 4 + 5 + 4);
+    assert_eq!(surfaces(&g), [Surface::SPHERE]);
+    assert!(g.is_oriented());
+
+    // Mobius strip
+    let mut g = GMap::empty(2).unwrap();
+    let s = g.add_polygon(4);
+    g.sew(2, g.al(s, [0, 1]), g.al(s, [1, 0])).unwrap();
+    g.cap_boundaries().unwrap();
+    assert_eq!(surfaces(&g), [Surface::PROJECTIVE_PLANE]);
+  }
 }
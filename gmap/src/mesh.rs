@@ -0,0 +1,353 @@
+use crate::build::{from_faces, Embedding};
+use crate::{Alphas, GMap, GMapError, GMapLike, OrbitMap};
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+type Position = (f64, f64, f64);
+
+/// A 2-map read from a mesh, with the position of each vertex.
+/// Vertices of the mesh which are on no face are dropped.
+pub type Mesh = (Embedding, OrbitMap<Position>);
+
+/// Faces of a mesh as lists of vertex indices, with the position of each vertex.
+struct Polygons {
+  positions: Vec<Position>,
+  faces: Vec<Vec<usize>>,
+}
+
+fn invalid<T>(s: String) -> Result<T, GMapError> {
+  Err(GMapError::InvalidMesh(s))
+}
+
+fn parse<T: std::str::FromStr>(token: Option<&str>, what: &str) -> Result<T, GMapError> {
+  match token.map(|t| t.parse()) {
+    Some(Ok(x)) => Ok(x),
+    Some(Err(_)) => invalid(format!("cannot parse {} {:?}", what, token.unwrap())),
+    None => invalid(format!("missing {}", what)),
+  }
+}
+
+impl Polygons {
+  fn build(self) -> Result<Mesh, GMapError> {
+    let n = self.positions.len();
+    if let Some(&v) = self.faces.iter().flatten().find(|&&v| v >= n) {
+      return invalid(format!("vertex {} out of range with {} vertices", v, n));
+    }
+    let e = from_faces(&self.faces)?;
+    let mut positions = OrbitMap::new(Alphas::VERTEX);
+    for (v, d) in e.vertices.iter().enumerate() {
+      if let Some(d) = d {
+        positions.insert(&e.g, *d, self.positions[v]);
+      }
+    }
+    Ok((e, positions))
+  }
+
+  /// The closed faces of g, with vertices numbered in order of `one_dart_per_cell`.
+  /// Faces go around in the same direction within each orientable component.
+  fn of(g: &GMap, positions: &OrbitMap<Position>) -> Result<Self, GMapError> {
+    let mut color = HashMap::new();
+    for d in g.one_dart_per_component() {
+      if let Ok(c) = g.orientation(d) {
+        color.extend(c);
+      }
+    }
+    let mut index = HashMap::new();
+    let mut vertices = vec![];
+    for (k, v) in g.one_dart_per_cell(0).enumerate() {
+      match positions.map().get(&v) {
+        Some(&p) => vertices.push(p),
+        None => return invalid(format!("no position for vertex of dart {}", v)),
+      }
+      for x in g.cell(v, 0) {
+        index.insert(x, k);
+      }
+    }
+    let faces = g
+      .one_dart_per_cell(2)
+      .filter(|&f| g.cell(f, 2).all(|x| !g.is_free(x, 1)))
+      .map(|f| {
+        let start = g
+          .cell(f, 2)
+          .filter(|x| color.get(x).cloned().unwrap_or(true))
+          .min()
+          .unwrap();
+        g.cycle(start, &[0, 1]).map(|x| index[&x]).collect()
+      })
+      .collect();
+    Ok(Self {
+      positions: vertices,
+      faces,
+    })
+  }
+}
+
+/// Lines of a mesh file without comments, or blank lines.
+fn lines(s: &str) -> impl Iterator<Item = &str> {
+  s.lines()
+    .map(|l| l.split('#').next().unwrap().trim())
+    .filter(|l| !l.is_empty())
+}
+
+/// Read a mesh in the OFF format.
+/// Faces are sewn together as for `build::from_faces`,
+/// and any colors after the vertex indices of a face are ignored.
+pub fn read_off(s: &str) -> Result<Mesh, GMapError> {
+  let mut lines = lines(s);
+  let mut header = lines.next().unwrap_or("").split_whitespace().peekable();
+  if header.next_if(|&t| t == "OFF").is_none() {
+    return invalid("missing OFF header".to_string());
+  }
+  // the counts may follow the header on the same line
+  let mut counts = match header.peek() {
+    Some(_) => header,
+    None => lines.next().unwrap_or("").split_whitespace().peekable(),
+  };
+  let nv: usize = parse(counts.next(), "number of vertices")?;
+  let nf: usize = parse(counts.next(), "number of faces")?;
+  let mut positions = vec![];
+  for _ in 0..nv {
+    let mut t = lines.next().unwrap_or("").split_whitespace();
+    let x = parse(t.next(), "vertex coordinate")?;
+    let y = parse(t.next(), "vertex coordinate")?;
+    let z = parse(t.next(), "vertex coordinate")?;
+    positions.push((x, y, z));
+  }
+  let mut faces = vec![];
+  for _ in 0..nf {
+    let mut t = lines.next().unwrap_or("").split_whitespace();
+    let k: usize = parse(t.next(), "face size")?;
+    let face = (0..k)
+      .map(|_| parse(t.next(), "face vertex"))
+      .collect::<Result<_, _>>()?;
+    faces.push(face);
+  }
+  Polygons { positions, faces }.build()
+}
+
+/// Read a mesh in the Wavefront OBJ format, from its vertices and faces.
+/// Texture coordinates and normals of face vertices, as well as other statements, are ignored.
+/// Faces are sewn together as for `build::from_faces`.
+pub fn read_obj(s: &str) -> Result<Mesh, GMapError> {
+  let mut positions = vec![];
+  let mut faces = vec![];
+  for line in lines(s) {
+    let mut t = line.split_whitespace();
+    match t.next() {
+      Some("v") => {
+        let x = parse(t.next(), "vertex coordinate")?;
+        let y = parse(t.next(), "vertex coordinate")?;
+        let z = parse(t.next(), "vertex coordinate")?;
+        positions.push((x, y, z));
+      }
+      Some("f") => {
+        let face = t
+          .map(|v| {
+            // indices count from 1, or back from the last vertex so far if negative
+            let i: isize = parse(v.split('/').next(), "face vertex")?;
+            match i {
+              1.. => Ok(i as usize - 1),
+              ..=-1 if i.unsigned_abs() <= positions.len() => {
+                Ok(positions.len() - i.unsigned_abs())
+              }
+              _ => invalid(format!("face vertex {} out of range", i)),
+            }
+          })
+          .collect::<Result<_, _>>()?;
+        faces.push(face);
+      }
+      _ => {}
+    }
+  }
+  Polygons { positions, faces }.build()
+}
+
+/// Write the closed faces of a 2-map in the OFF format, given the position of each vertex.
+/// Vertices are numbered in order of `one_dart_per_cell`,
+/// and faces go around in the same direction within each orientable component.
+pub fn write_off(g: &GMap, positions: &OrbitMap<Position>) -> Result<String, GMapError> {
+  let p = Polygons::of(g, positions)?;
+  let mut s = format!("OFF\n{} {} 0\n", p.positions.len(), p.faces.len());
+  for (x, y, z) in p.positions {
+    writeln!(s, "{} {} {}", x, y, z).unwrap();
+  }
+  for face in p.faces {
+    write!(s, "{}", face.len()).unwrap();
+    for v in face {
+      write!(s, " {}", v).unwrap();
+    }
+    s.push('\n');
+  }
+  Ok(s)
+}
+
+/// Write the closed faces of a 2-map in the Wavefront OBJ format, as for `write_off`.
+pub fn write_obj(g: &GMap, positions: &OrbitMap<Position>) -> Result<String, GMapError> {
+  let p = Polygons::of(g, positions)?;
+  let mut s = String::new();
+  for (x, y, z) in p.positions {
+    writeln!(s, "v {} {} {}", x, y, z).unwrap();
+  }
+  for face in p.faces {
+    s.push('f');
+    for v in face {
+      write!(s, " {}", v + 1).unwrap();
+    }
+    s.push('\n');
+  }
+  Ok(s)
+}
+
+/// Positions in the plane z = 0 of a layout, for writing out.
+pub fn lift(layout: &OrbitMap<(f64, f64)>) -> OrbitMap<Position> {
+  layout.map_values(|&(x, y)| (x, y, 0.))
+}
+
+/// Orthographic projection of positions onto the plane through the origin perpendicular to `normal`,
+/// seen from the side that `normal` points to, to use as a layout.
+/// Projecting along the z axis keeps the x and y coordinates.
+pub fn project(positions: &OrbitMap<Position>, normal: Position) -> OrbitMap<(f64, f64)> {
+  let cross = |a: Position, b: Position| {
+    (
+      a.1 * b.2 - a.2 * b.1,
+      a.2 * b.0 - a.0 * b.2,
+      a.0 * b.1 - a.1 * b.0,
+    )
+  };
+  let dot = |a: Position, b: Position| a.0 * b.0 + a.1 * b.1 + a.2 * b.2;
+  let normalize = |a: Position| {
+    let l = dot(a, a).sqrt();
+    (a.0 / l, a.1 / l, a.2 / l)
+  };
+  let n = normalize(normal);
+  let e0 = cross((0., 1., 0.), n);
+  let e0 = normalize(if dot(e0, e0) > 1e-12 {
+    e0
+  } else {
+    cross(n, (0., 0., 1.))
+  });
+  let e1 = cross(n, e0);
+  positions.map_values(|&p| (dot(p, e0), dot(p, e1)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::polyhedron::{polyhedron, Solid};
+  use crate::grids::square;
+  use crate::isomorphism::is_isomorphic;
+  use crate::topology::{self, Surface};
+
+  #[test]
+  fn test_off() {
+    let p = polyhedron(Solid::TruncatedOctahedron).unwrap();
+    let s = write_off(&p.g, &p.positions).unwrap();
+    assert!(s.starts_with("OFF\n24 14 0\n"));
+    let (e, positions) = read_off(&s).unwrap();
+    assert!(is_isomorphic(&e.g, &p.g));
+    assert!(e.g.is_oriented());
+    for v in e.g.one_dart_per_cell(0) {
+      let (x, y, z) = positions.map()[&v];
+      assert!((x * x + y * y + z * z - 2.5).abs() < 1e-9);
+    }
+
+    let s = "
+      OFF # a square and a triangle on the same edge, with colors
+      5 2 0
+      0 0 0
+      1 0 0
+      1 1 0
+      0 1 0
+      2 0.5 0
+      4 0 1 2 3 255 0 0
+      3 1 4 2
+    ";
+    let (e, positions) = read_off(s).unwrap();
+    let c = topology::components(&e.g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (5, 6, 2));
+    assert_eq!(positions.map()[&e.vertices[4].unwrap()], (2., 0.5, 0.));
+    assert_eq!(positions.map()[&e.faces[1]], (1., 0., 0.));
+
+    assert!(matches!(
+      read_off("OFF\n1 1 0\n0 0 0\n3 0 1 2"),
+      Err(GMapError::InvalidMesh(_))
+    ));
+    assert!(matches!(
+      read_off("OFF\n1 0 0\n0 zero 0"),
+      Err(GMapError::InvalidMesh(_))
+    ));
+    assert!(matches!(
+      read_off("OFF\n4 0 0\n"),
+      Err(GMapError::InvalidMesh(_))
+    ));
+    assert!(matches!(read_off("PLY\n"), Err(GMapError::InvalidMesh(_))));
+  }
+
+  #[test]
+  fn test_obj() {
+    let s = "
+      # a square pyramid
+      o pyramid
+      v 0 0 0
+      v 1 0 0
+      v 1 1 0
+      v 0 1 0
+      v 0.5 0.5 1
+      vn 0 0 1
+      f 4/1/1 3/2/1 2//1 1
+      f 1 2 5
+      f -4 -3 -1
+      f 3 4 5
+      f -1 4 1
+    ";
+    let (e, positions) = read_obj(s).unwrap();
+    let c = topology::components(&e.g).unwrap();
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (5, 8, 5));
+    assert_eq!(c[0].surface(), Some(Surface::SPHERE));
+    assert_eq!(positions.map()[&e.vertices[4].unwrap()], (0.5, 0.5, 1.));
+
+    let written = write_obj(&e.g, &positions).unwrap();
+    let (e1, _) = read_obj(&written).unwrap();
+    assert!(is_isomorphic(&e.g, &e1.g));
+    assert!(e1.g.is_oriented());
+
+    assert!(matches!(
+      read_obj("v 0 0 0\nf 1 2 -3"),
+      Err(GMapError::InvalidMesh(_))
+    ));
+    assert!(matches!(
+      read_obj("v 0 0 0\nf 0 1"),
+      Err(GMapError::InvalidMesh(_))
+    ));
+    assert!(matches!(
+      read_obj("v 0 0 0\nf -9223372036854775808 1 1"),
+      Err(GMapError::InvalidMesh(_))
+    ));
+  }
+
+  #[test]
+  fn test_layouts() {
+    // a grid with boundary, written with its layout and projected back
+    let (g, rows) = square::new(2, 3);
+    let mut layout = OrbitMap::new(Alphas::VERTEX);
+    for (r, row) in square::vertex_grid(&g, &rows).iter().enumerate() {
+      for (c, &v) in row.iter().enumerate() {
+        layout.insert(&g, v, (c as f64, r as f64));
+      }
+    }
+    let (e, positions) = read_off(&write_off(&g, &lift(&layout)).unwrap()).unwrap();
+    assert!(is_isomorphic(&e.g, &g));
+    let projected = project(&positions, (0., 0., 1.));
+    for v in e.g.one_dart_per_cell(0) {
+      let (x, y, z) = positions.map()[&v];
+      assert_eq!(z, 0.);
+      assert_eq!(projected.map()[&v], (x, y));
+    }
+
+    let side = project(&positions, (0., 2., 0.));
+    for (d, &(x, y)) in side.map() {
+      assert_eq!((x, y), (positions.map()[d].0, 0.));
+    }
+  }
+}
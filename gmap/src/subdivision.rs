@@ -0,0 +1,215 @@
+use crate::{Dart, GMap, GMapError, GMapLike};
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+/// Where a dart of a barycentric subdivision comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin<D> {
+  /// Dart of the original map whose simplex contains the new dart
+  pub dart: D,
+  /// The vertices of the new dart's simplex are the centers of the cells of `dart`.
+  /// cells[k] is the dimension of the cell whose center is the k-th vertex along the new dart,
+  /// so in particular the new dart's vertex is the center of the cells[0]-cell of `dart`.
+  pub cells: Vec<usize>,
+}
+
+/// Barycentric subdivision of g.
+/// Each dart of g becomes an n-simplex whose vertices are the centers of its i-cells,
+/// which is made up of (n+1)! new darts, one for each order of those vertices.
+/// Also returns the origin of each new dart, indexed by dart number.
+pub fn barycentric<G: GMapLike + ?Sized>(g: &G) -> (GMap, Vec<Origin<G::Dart>>) {
+  let n = g.dimension();
+  let darts: Vec<G::Dart> = g.darts().collect();
+  let index: HashMap<G::Dart, usize> = darts.iter().enumerate().map(|(k, &d)| (d, k)).collect();
+  let perms: Vec<Vec<usize>> = (0..=n).permutations(n + 1).collect();
+  let rank: HashMap<&[usize], usize> = perms
+    .iter()
+    .enumerate()
+    .map(|(k, p)| (p.as_slice(), k))
+    .collect();
+  let new = |d: G::Dart, p: &[usize]| Dart(index[&d] * perms.len() + rank[p]);
+
+  let mut s = GMap::empty(n).unwrap();
+  let mut origins = vec![];
+  for &d in &darts {
+    for p in &perms {
+      s.add_dart();
+      origins.push(Origin {
+        dart: d,
+        cells: p.clone(),
+      });
+    }
+  }
+  for &d in &darts {
+    for p in &perms {
+      let x = new(d, p);
+      // alpha_k within the simplex exchanges the k-th and (k+1)-th vertices
+      for k in 0..n {
+        let mut q = p.clone();
+        q.swap(k, k + 1);
+        *s.al1(x, k) = new(d, &q);
+      }
+      // alpha_n crosses the facet opposite the last vertex into the neighboring simplex
+      *s.al1(x, n) = new(g.alpha(d, p[n]), p);
+    }
+  }
+  debug_assert!(s.check_valid().is_ok());
+  (s, origins)
+}
+
+/// Triangulate each face of a 2-map by adding diagonals from a single vertex.
+/// Faces which are not closed (have 1-free darts) are left alone.
+/// Returns the triangulation together with a mapping from its darts to darts of g
+/// sharing the same vertex and face.
+pub fn triangulate_fan<G: GMapLike + ?Sized>(
+  g: &G,
+) -> Result<(GMap, HashMap<Dart, G::Dart>), GMapError> {
+  triangulate(g, |_| 1)
+}
+
+/// Triangulate each face of a 2-map by ear clipping,
+/// given the position of the vertex of each dart,
+/// so that every triangle lies inside its face.
+/// Faces must be simple polygons, except that degenerate faces are cut as fans.
+/// Faces which are not closed (have 1-free darts) are left alone.
+/// Returns the triangulation together with a mapping from its darts to darts of g
+/// sharing the same vertex and face.
+pub fn triangulate_ears<G, P>(
+  g: &G,
+  position: P,
+) -> Result<(GMap, HashMap<Dart, G::Dart>), GMapError>
+where
+  G: GMapLike + ?Sized,
+  P: Fn(G::Dart) -> (f64, f64),
+{
+  triangulate(g, |ds| {
+    let p: Vec<(f64, f64)> = ds.iter().map(|&d| position(d)).collect();
+    let k = p.len();
+    let area: f64 = (0..k).map(|j| cross(p[j], p[(j + 1) % k])).sum();
+    (0..k)
+      .find(|&j| {
+        let (a, b, c) = (p[(j + k - 1) % k], p[j], p[(j + 1) % k]);
+        let convex = (cross(a, b) + cross(b, c) + cross(c, a)) * area > 0.;
+        convex
+          && p
+            .iter()
+            .all(|&q| q == a || q == b || q == c || !in_triangle(q, a, b, c))
+      })
+      .unwrap_or(1)
+  })
+}
+
+fn cross(a: (f64, f64), b: (f64, f64)) -> f64 {
+  a.0 * b.1 - a.1 * b.0
+}
+
+/// Whether q is inside or on the boundary of the triangle abc.
+fn in_triangle(q: (f64, f64), a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> bool {
+  let side = |u: (f64, f64), v: (f64, f64)| cross((v.0 - u.0, v.1 - u.1), (q.0 - u.0, q.1 - u.1));
+  let (s0, s1, s2) = (side(a, b), side(b, c), side(c, a));
+  (s0 >= 0. && s1 >= 0. && s2 >= 0.) || (s0 <= 0. && s1 <= 0. && s2 <= 0.)
+}
+
+/// Triangulate each closed face of g by repeatedly cutting off an ear.
+/// ear is given the darts of g around the remaining face, one per vertex,
+/// and returns the index of the vertex to cut off.
+fn triangulate<G, E>(g: &G, ear: E) -> Result<(GMap, HashMap<Dart, G::Dart>), GMapError>
+where
+  G: GMapLike + ?Sized,
+  E: Fn(&[G::Dart]) -> usize,
+{
+  if g.dimension() != 2 {
+    return Err(GMapError::WrongDimension(2));
+  }
+  let (mut t, m) = g.to_gmap();
+  let mut back: HashMap<Dart, G::Dart> = m.iter().map(|(&x, &y)| (y, x)).collect();
+  let faces: Vec<G::Dart> = g.one_dart_per_cell(2).collect();
+  for f in faces {
+    if g.cycle(f, &[0, 1]).any(|x| g.is_free(x, 1)) {
+      continue;
+    }
+    let mut ds: Vec<Dart> = t.cycle(m[&f], &[0, 1]).collect();
+    while ds.len() > 3 {
+      let k = ds.len();
+      let j = ear(&ds.iter().map(|d| back[d]).collect::<Vec<_>>());
+      let (d0, d1) = (ds[(j + k - 1) % k], ds[(j + 1) % k]);
+      let p0 = t.split_face(d0, d1)?;
+      let q0 = t[(p0, 2)];
+      for x in [p0, t[(p0, 0)], q0, t[(q0, 0)]] {
+        back.insert(x, back[&t[(x, 1)]]);
+      }
+      ds[(j + k - 1) % k] = q0;
+      ds.remove(j);
+    }
+  }
+  Ok((t, back))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::square;
+  use crate::topology;
+  use crate::Alphas;
+
+  #[test]
+  fn test_barycentric() {
+    let (g, _) = square::new(2, 2);
+    let (s, origins) = barycentric(&g);
+    assert_eq!(s.darts().count(), 32 * 6);
+    assert!(s.one_dart_per_cell(2).all(|f| s.cell(f, 2).count() == 6));
+    let c = topology::components(&s).unwrap();
+    assert_eq!(c[0].vertices, 9 + 12 + 4);
+    assert_eq!(c[0].surface(), Some(topology::Surface::DISK));
+
+    // each new vertex is the center of a single original cell
+    for v in s.one_dart_per_cell(0) {
+      let Origin { dart, cells } = &origins[v.0];
+      let cell: Vec<Dart> = g.cell(*dart, cells[0]).sorted().collect();
+      for x in s.cell(v, 0) {
+        assert_eq!(origins[x.0].cells[0], cells[0]);
+        assert!(cell.contains(&origins[x.0].dart));
+      }
+    }
+  }
+
+  #[test]
+  fn test_triangulate() {
+    let mut g = GMap::empty(2).unwrap();
+    let f = g.add_polygon(6);
+    let (t, back) = triangulate_fan(&g).unwrap();
+    assert_eq!(t.one_dart_per_cell(2).count(), 4);
+    assert!(t
+      .one_dart_per_cell(2)
+      .all(|x| t.cycle(x, &[0, 1]).count() == 3));
+    assert_eq!(t.cell(f, 0).count(), 2 * 4);
+    for x in t.darts() {
+      assert!(g
+        .orbit(back[&x], Alphas::VERTEX)
+        .contains(&back[&t[(x, 1)]]));
+    }
+
+    // an L-shaped hexagon, which cannot be cut as a fan from f's vertex
+    let points = [(2., 0.), (2., 1.), (1., 1.), (1., 2.), (0., 2.), (0., 0.)];
+    let corners: Vec<Dart> = g.cycle(f, &[0, 1]).collect();
+    let position = |d| {
+      points[corners
+        .iter()
+        .position(|&c| g.cell(c, 0).contains(&d))
+        .unwrap()]
+    };
+    // total unsigned area of the triangles, which is the area of the hexagon iff they don't overlap
+    let area = |(t, back): (GMap, HashMap<Dart, Dart>)| -> f64 {
+      t.one_dart_per_cell(2)
+        .map(|x| {
+          let p: Vec<(f64, f64)> = t.cycle(x, &[0, 1]).map(|y| position(back[&y])).collect();
+          (cross(p[0], p[1]) + cross(p[1], p[2]) + cross(p[2], p[0])).abs() / 2.
+        })
+        .sum()
+    };
+    assert!(area(triangulate_fan(&g).unwrap()) > 3.);
+    assert_eq!(area(triangulate_ears(&g, position).unwrap()), 3.);
+  }
+}
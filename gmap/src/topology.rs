@@ -0,0 +1,278 @@
+use crate::{Alphas, GMapError, GMapLike};
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// Topological invariants of one connected component of a 2-map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Component<D> {
+  /// Lowest-numbered dart of the component
+  pub dart: D,
+  pub vertices: usize,
+  pub edges: usize,
+  pub faces: usize,
+  /// Number of boundary components, i.e. cycles of 2-free darts
+  pub boundaries: usize,
+  pub orientable: bool,
+}
+
+impl<D> Component<D> {
+  /// V - E + F
+  pub fn euler_characteristic(&self) -> isize {
+    self.vertices as isize - self.edges as isize + self.faces as isize
+  }
+
+  /// The surface which the component makes up,
+  /// or None if no surface has its Euler characteristic and orientability,
+  /// as for components which are not surfaces.
+  pub fn surface(&self) -> Option<Surface> {
+    // capping each boundary with a disk adds one face
+    let closed = self.euler_characteristic() + self.boundaries as isize;
+    let boundaries = self.boundaries;
+    // closed surfaces have Euler characteristic 2 minus twice the genus or the number of crosscaps
+    let k = usize::try_from(2 - closed).ok()?;
+    if self.orientable {
+      (k % 2 == 0).then_some(Surface::Orientable {
+        genus: k / 2,
+        boundaries,
+      })
+    } else {
+      (k > 0).then_some(Surface::NonOrientable {
+        crosscaps: k,
+        boundaries,
+      })
+    }
+  }
+}
+
+/// Classification of a compact connected surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Surface {
+  /// Sphere with `genus` handles and `boundaries` holes
+  Orientable { genus: usize, boundaries: usize },
+  /// Sphere with `crosscaps` crosscaps and `boundaries` holes
+  NonOrientable { crosscaps: usize, boundaries: usize },
+}
+
+impl Surface {
+  pub const SPHERE: Self = Self::Orientable {
+    genus: 0,
+    boundaries: 0,
+  };
+  pub const DISK: Self = Self::Orientable {
+    genus: 0,
+    boundaries: 1,
+  };
+  pub const CYLINDER: Self = Self::Orientable {
+    genus: 0,
+    boundaries: 2,
+  };
+  pub const TORUS: Self = Self::Orientable {
+    genus: 1,
+    boundaries: 0,
+  };
+  pub const PROJECTIVE_PLANE: Self = Self::NonOrientable {
+    crosscaps: 1,
+    boundaries: 0,
+  };
+  pub const MOBIUS_STRIP: Self = Self::NonOrientable {
+    crosscaps: 1,
+    boundaries: 1,
+  };
+  pub const KLEIN_BOTTLE: Self = Self::NonOrientable {
+    crosscaps: 2,
+    boundaries: 0,
+  };
+
+  /// Common name of the surface, if it has one
+  pub fn name(&self) -> Option<&'static str> {
+    Some(match *self {
+      Self::SPHERE => "sphere",
+      Self::DISK => "disk",
+      Self::CYLINDER => "cylinder",
+      Self::TORUS => "torus",
+      Self::PROJECTIVE_PLANE => "projective plane",
+      Self::MOBIUS_STRIP => "Möbius strip",
+      Self::KLEIN_BOTTLE => "Klein bottle",
+      _ => return None,
+    })
+  }
+
+  pub fn is_orientable(&self) -> bool {
+    matches!(self, Self::Orientable { .. })
+  }
+
+  pub fn boundaries(&self) -> usize {
+    match *self {
+      Self::Orientable { boundaries, .. } | Self::NonOrientable { boundaries, .. } => boundaries,
+    }
+  }
+}
+
+impl fmt::Display for Surface {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if let Some(name) = self.name() {
+      return write!(f, "{}", name);
+    }
+    match *self {
+      Self::Orientable { genus, boundaries } => {
+        write!(f, "orientable surface of genus {}", genus)?;
+        if boundaries > 0 {
+          write!(f, " with {} boundary components", boundaries)?;
+        }
+      }
+      Self::NonOrientable {
+        crosscaps,
+        boundaries,
+      } => {
+        write!(f, "nonorientable surface with {} crosscaps", crosscaps)?;
+        if boundaries > 0 {
+          write!(f, " and {} boundary components", boundaries)?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The next 2-free dart along the boundary after d, at the other end of d's edge.
+pub fn boundary_next<G: GMapLike + ?Sized>(g: &G, d: G::Dart) -> G::Dart {
+  let mut e = g.alpha(d, 0);
+  loop {
+    e = g.alpha(e, 1);
+    if g.is_free(e, 2) {
+      return e;
+    }
+    e = g.alpha(e, 2);
+  }
+}
+
+/// The boundary component containing the 2-free dart d:
+/// the cycle of 2-free darts starting from d, one per edge, each followed by `boundary_next`.
+pub fn boundary<G: GMapLike + ?Sized>(g: &G, d: G::Dart) -> Vec<G::Dart> {
+  let mut cycle = vec![d];
+  let mut x = boundary_next(g, d);
+  while x != d {
+    cycle.push(x);
+    x = boundary_next(g, x);
+  }
+  cycle
+}
+
+/// Boundary components among the given darts of a 2-map, as for `boundary`,
+/// each starting from its first 2-free dart in the given order.
+/// Each boundary is traversed in only one of its two directions.
+fn boundaries_among<G: GMapLike + ?Sized>(
+  g: &G,
+  darts: impl IntoIterator<Item = G::Dart>,
+) -> Vec<Vec<G::Dart>> {
+  let mut seen = HashSet::new();
+  let mut result = vec![];
+  for x in darts {
+    if !g.is_free(x, 2) || seen.contains(&x) {
+      continue;
+    }
+    let cycle = boundary(g, x);
+    for &y in &cycle {
+      seen.insert(y);
+      seen.insert(g.alpha(y, 0));
+    }
+    result.push(cycle);
+  }
+  result
+}
+
+/// All boundary components of a 2-map, as for `boundary`,
+/// in order of their lowest-numbered darts.
+/// Each boundary is traversed in only one of its two directions.
+pub fn boundaries<G: GMapLike + ?Sized>(g: &G) -> Result<Vec<Vec<G::Dart>>, GMapError> {
+  if g.dimension() != 2 {
+    return Err(GMapError::WrongDimension(2));
+  }
+  Ok(boundaries_among(g, g.darts()))
+}
+
+/// Compute the invariants of each connected component of a 2-map,
+/// in order of their lowest-numbered darts.
+pub fn components<G: GMapLike + ?Sized>(g: &G) -> Result<Vec<Component<G::Dart>>, GMapError> {
+  if g.dimension() != 2 {
+    return Err(GMapError::WrongDimension(2));
+  }
+  Ok(
+    g.one_dart_per_component()
+      .map(|d| {
+        let darts: Vec<G::Dart> = g.orbit(d, Alphas(!0)).collect();
+        let count = |a| g.unique_by_orbit(darts.iter().cloned(), a).count();
+        Component {
+          dart: d,
+          vertices: count(Alphas::VERTEX),
+          edges: count(Alphas::EDGE),
+          faces: count(Alphas::FACE),
+          boundaries: boundaries_among(g, darts.iter().cloned()).len(),
+          orientable: g.orientation(d).is_ok(),
+        }
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grids::square;
+  use crate::GMap;
+
+  #[test]
+  fn test_surfaces() {
+    let (g, _) = square::new(3, 4);
+    let c = components(&g).unwrap();
+    assert_eq!(c.len(), 1);
+    assert_eq!((c[0].vertices, c[0].edges, c[0].faces), (20, 31, 12));
+    assert_eq!(c[0].euler_characteristic(), 1);
+    assert_eq!(c[0].surface(), Some(Surface::DISK));
+
+    let mut g = GMap::empty(2).unwrap();
+    let s = g.add_polygon(4);
+    g.sew(2, g.al(s, [0, 1]), g.al(s, [1])).unwrap();
+    let t = g.add_polygon(4);
+    g.sew(2, g.al(t, [0, 1]), g.al(t, [1, 0])).unwrap();
+    let c = components(&g).unwrap();
+    assert_eq!(c.len(), 2);
+    assert_eq!(c[0].surface(), Some(Surface::CYLINDER));
+    assert_eq!(c[1].surface(), Some(Surface::MOBIUS_STRIP));
+    assert_eq!(c[1].surface().unwrap().to_string(), "Möbius strip");
+
+    // glue the remaining sides of each
+    g.sew(2, s, g.al(s, [1, 0, 1])).unwrap();
+    g.sew(2, t, g.al(t, [1, 0, 1])).unwrap();
+    let c = components(&g).unwrap();
+    assert_eq!(c[0].surface(), Some(Surface::TORUS));
+    assert_eq!(c[1].surface(), Some(Surface::KLEIN_BOTTLE));
+    assert_eq!(c[1].euler_characteristic(), 0);
+
+    // counts of no surface
+    let c = Component {
+      dart: 0,
+      vertices: 3,
+      edges: 0,
+      faces: 0,
+      boundaries: 0,
+      orientable: true,
+    };
+    assert_eq!(c.surface(), None);
+    assert_eq!(Component { vertices: 1, ..c }.surface(), None);
+    assert_eq!(
+      Component {
+        vertices: 2,
+        orientable: false,
+        ..c
+      }
+      .surface(),
+      None
+    );
+
+    let mut g = GMap::empty(3).unwrap();
+    g.add_polygon(3);
+    components(&g).unwrap_err();
+  }
+}
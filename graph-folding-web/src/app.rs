@@ -1,4 +1,4 @@
-use gmap::{Alphas, Dart, GMap, OrbitReprs};
+use gmap::{Alphas, Dart, GMap, GMapLike, OrbitReprs};
 use graph_folding::{examples, Angle, Color, Constraints, Length, Problem};
 
 use std::collections::HashMap;
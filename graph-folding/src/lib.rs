@@ -4,7 +4,8 @@ use circular::{Circular, Data, Node};
 
 use std::collections::HashMap;
 
-use gmap::{Alphas, Dart, GMap, OrbitReprs};
+use gmap::topology::{self, Surface};
+use gmap::{Alphas, Dart, GMap, GMapLike, OrbitReprs};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,6 +16,8 @@ pub enum Error {
   KawasakiViolation,
   #[error("Graph must be planar")]
   Nonplanar,
+  #[error("Lower-numbered darts of all angles must have the same orientation")]
+  Unoriented,
   #[error(transparent)]
   GMap(#[from] gmap::GMapError),
 }
@@ -65,9 +68,12 @@ impl Problem {
     angle_constraints: HashMap<Dart, Angle>,
     exterior_face: Dart,
   ) -> Result<Self, Error> {
-    if g.dimension() != 2 {
-      // should implement more robust planarity checking
-      return Err(Error::Nonplanar);
+    match topology::components(&g) {
+      Ok(c) if c.iter().all(|c| c.surface() == Some(Surface::SPHERE)) => {}
+      _ => return Err(Error::Nonplanar),
+    }
+    if !g.is_oriented() {
+      return Err(Error::Unoriented);
     }
     let mut or = OrbitReprs::new();
     or.ensure_all(&g, Alphas::ANGLE);
@@ -433,35 +439,12 @@ pub mod examples {
 
   use std::collections::HashMap;
 
-  use gmap::{Alphas, Dart, GMap, OrbitReprs, grids::square};
+  use gmap::{Alphas, Dart, GMap, GMapLike, OrbitReprs, grids::square};
 
   // start should be a counterclockwise dart
+  #[deprecated(note = "use `GMap::cap_boundary`")]
   pub fn wrap_exterior(g: &mut GMap, start: Dart) -> Dart {
-    let mut interior_boundary = vec![];
-    // start with a clockwise dart
-    let start = g[(start, 0)];
-    let mut d = start;
-    loop {
-      // traverse clockwise
-      interior_boundary.push(d);
-      d = if let Some(n) = g.cell(d, 0).find(|&n| n != d && g.is_free(n, 2)) {
-        n
-      } else {
-        break;
-      };
-      d = g[(d, 0)];
-      if d == start {
-        break;
-      }
-    }
-    let ext = g.add_polygon(interior_boundary.len());
-    let mut d2 = ext;
-    for d in interior_boundary {
-      g.sew(2, d, d2).unwrap();
-      // clockwise for the interior is counterclockwise around the exterior
-      d2 = g.al(d2, [1, 0]);
-    }
-    ext
+    g.cap_boundary(start).unwrap()
   }
 
   // TODO abstract single-polygon examples into a single function
@@ -472,7 +455,7 @@ pub mod examples {
     let angle_constraints = HashMap::new();
 
     let f = g.add_polygon(4);
-    let ext = wrap_exterior(&mut g, f);
+    let ext = g.cap_boundary(f).unwrap();
 
     let mut or = OrbitReprs::new();
     or.ensure_all(&g, Alphas::EDGE);
@@ -495,7 +478,7 @@ pub mod examples {
     let angle_constraints = HashMap::new();
 
     let f = g.add_polygon(4);
-    let ext = wrap_exterior(&mut g, f);
+    let ext = g.cap_boundary(f).unwrap();
 
     let mut or = OrbitReprs::new();
     or.ensure_all(&g, Alphas::EDGE);
@@ -517,7 +500,7 @@ pub mod examples {
     let mut edge_lengths = HashMap::new();
     let angle_constraints = HashMap::new();
 
-    let ext = wrap_exterior(&mut g, squares[0][0]);
+    let ext = g.cap_boundary(squares[0][0]).unwrap();
 
     // let mut or = OrbitReprs::new();
     // or.ensure_all(&g, Alphas::EDGE);
@@ -534,7 +517,7 @@ pub mod examples {
     let angle_constraints = HashMap::new();
 
     let f = g.add_polygon(2 * n);
-    let ext = wrap_exterior(&mut g, f);
+    let ext = g.cap_boundary(f).unwrap();
 
     let mut or = OrbitReprs::new();
     or.ensure_all(&g, Alphas::EDGE);
@@ -560,7 +543,7 @@ pub mod examples {
     let angle_constraints = HashMap::new();
 
     let f = g.add_polygon(2 * n);
-    let ext = wrap_exterior(&mut g, f);
+    let ext = g.cap_boundary(f).unwrap();
 
     let mut or = OrbitReprs::new();
     or.ensure_all(&g, Alphas::EDGE);
@@ -581,7 +564,7 @@ pub mod examples {
     let angle_constraints = HashMap::new();
 
     let f = g.add_polygon(2 * n);
-    let ext = wrap_exterior(&mut g, f);
+    let ext = g.cap_boundary(f).unwrap();
 
     let mut or = OrbitReprs::new();
     or.ensure_all(&g, Alphas::EDGE);